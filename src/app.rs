@@ -5,10 +5,18 @@ use egui_winit::winit::{
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
-use std::{iter, sync::Arc};
+use std::{iter, path::Path, sync::Arc};
 
-use crate::gpu::{Connection, RenderTarget};
-use crate::ray_tracer::Scene;
+use crate::args::Args;
+use crate::gpu::{
+    export_aovs, read_display, Aov, Connection, Denoiser, Display, GpuTimer, RenderTarget,
+    SampleCounter, Timestamp, Tracer,
+};
+use crate::progress::Progress;
+use crate::ray_tracer::{Scene, Viewport};
+use crate::sequence::{FrameFormat, SequenceRender};
+use crate::settings::{Request, Settings};
+use crate::tiled::TiledRender;
 
 struct Initial {
     window: Arc<Window>,
@@ -21,6 +29,7 @@ struct GpuSetup {
     surface_config: wgpu::SurfaceConfiguration,
     device: wgpu::Device,
     queue: Arc<wgpu::Queue>,
+    supports_compute: bool,
 }
 
 struct UiSetup {
@@ -29,6 +38,7 @@ struct UiSetup {
     surface_config: wgpu::SurfaceConfiguration,
     device: wgpu::Device,
     queue: Arc<wgpu::Queue>,
+    supports_compute: bool,
 
     ui: crate::ui::Ui,
     egui_winit_state: egui_winit::State,
@@ -48,16 +58,26 @@ pub struct App {
     egui_context: egui::Context,
     egui_renderer: Renderer,
 
-    render_pipeline: wgpu::RenderPipeline,
-    previous_render_texture: wgpu::Texture,
+    tracer: Tracer,
     connection: Connection,
     render_target: RenderTarget,
+    denoiser: Denoiser,
+    display: Display,
+    /// None if the GPU can't time its passes.
+    timer: Option<GpuTimer>,
+    counter: SampleCounter,
 
     scene: Scene,
+    settings: Settings,
+    progress: Progress,
+    tiled_render: Option<TiledRender>,
+    sequence_render: Option<SequenceRender>,
+    /// Set when rendering an animation from the command line.
+    exit_after_sequence: bool,
 }
 
 impl Initial {
-    pub fn new(window: Arc<Window>) -> Self {
+    pub const fn new(window: Arc<Window>) -> Self {
         Self { window }
     }
 
@@ -72,9 +92,26 @@ impl Initial {
             .await
             .context("Failed to find an appropriate adapter")?;
 
+        let supports_compute = Tracer::supports_compute(&adapter);
+
+        let mut required_features = wgpu::Features::empty();
+        if supports_compute {
+            required_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        }
+        // timing the passes is optional, so only asked for when it's available
+        if adapter.features().contains(GpuTimer::FEATURES) {
+            required_features |= GpuTimer::FEATURES;
+        }
+
         // Create the logical device and command queue
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features,
+                    ..Default::default()
+                },
+                None,
+            )
             .await?;
 
         let queue = Arc::new(queue);
@@ -100,6 +137,7 @@ impl Initial {
             surface_config,
             device,
             queue,
+            supports_compute,
         })
     }
 }
@@ -124,6 +162,7 @@ impl GpuSetup {
             surface_config: self.surface_config,
             device: self.device,
             queue: self.queue,
+            supports_compute: self.supports_compute,
 
             ui,
             egui_winit_state,
@@ -134,52 +173,35 @@ impl GpuSetup {
 }
 
 impl UiSetup {
-    pub fn renderer_setup(self, initial_render_size: (u32, u32), scene: Scene) -> Result<App> {
-        let render_target = crate::gpu::RenderTarget::new(&self.device, initial_render_size);
-        let (previous_render_texture, previous_render_view) =
-            RenderTarget::create_render_texture(&self.device, render_target.size);
-
-        let connection = Connection::new(
-            &scene,
+    /// Create the renderer, using the compute tracer if `use_compute` and it is supported.
+    pub fn renderer_setup(
+        self,
+        initial_render_size: (u32, u32),
+        scene: Scene,
+        use_compute: bool,
+    ) -> Result<App> {
+        let render_target = crate::gpu::RenderTarget::new(
             &self.device,
-            self.queue.clone(),
-            &previous_render_view,
-        )?;
+            initial_render_size,
+            use_compute && self.supports_compute,
+        );
 
-        let pipeline_layout = self
-            .device
-            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                bind_group_layouts: &[&connection.bind_group_layout],
-                ..Default::default()
-            });
+        let connection = Connection::new(&scene, &self.device, &self.queue)?;
 
-        let render_pipeline = self
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &crate::gpu::vert_shader(&self.device),
-                    entry_point: "vs_main",
-                    buffers: &[Connection::vertex_buffer_layout()],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &crate::gpu::frag_shader(&self.device),
-                    entry_point: "fs_main",
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                        blend: None,
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    cull_mode: Some(wgpu::Face::Back),
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            });
+        let tracer = Tracer::new(&self.device, &connection, &render_target);
+        println!("Tracing with the {} tracer", tracer.name());
+
+        let denoiser = Denoiser::new(&self.device, &render_target);
+        let display = Display::new(&self.device, &render_target);
+        let timer = GpuTimer::new(&self.device, &self.queue);
+        if timer.is_none() {
+            println!("Timestamp queries are unsupported, so GPU timings won't be shown");
+        }
+
+        let counter = SampleCounter::new(&self.device, render_target.size);
+
+        let mut progress = Progress::new();
+        progress.statistics.gpu_timing = timer.is_some();
 
         Ok(App {
             window: self.window,
@@ -193,12 +215,20 @@ impl UiSetup {
             egui_context: self.egui_context,
             egui_renderer: self.egui_renderer,
 
-            render_pipeline,
-            previous_render_texture,
+            tracer,
             connection,
             render_target,
+            denoiser,
+            display,
+            timer,
+            counter,
 
             scene,
+            settings: Settings::default(),
+            progress,
+            tiled_render: None,
+            sequence_render: None,
+            exit_after_sequence: false,
         })
     }
 }
@@ -208,51 +238,318 @@ impl App {
     fn render_scene(&mut self) {
         puffin::profile_function!();
 
-        self.connection
-            .update_buffers(&self.queue, self.render_target.size, &self.scene);
+        let viewport = self.update_tiled_render();
+        self.update_sequence_render();
+
+        if viewport.size != self.render_target.size {
+            self.render_target.resize(&self.device, viewport.size);
+        }
+        if self.denoiser.size != self.render_target.size {
+            self.denoiser.resize(&self.device, &self.render_target);
+        }
+        if self.display.bloom.size != self.render_target.size {
+            self.display
+                .bloom
+                .resize(&self.device, self.render_target.size);
+        }
+
+        let timings = self
+            .timer
+            .as_mut()
+            .and_then(|timer| timer.read(&self.device));
 
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
-        {
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &self.render_target.render_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations::default(),
-                })],
-                ..Default::default()
-            });
-
-            rpass.set_pipeline(&self.render_pipeline);
-            rpass.set_bind_group(0, &self.connection.bind_group, &[]);
+        self.scene.update_motion();
 
-            rpass.set_vertex_buffer(0, self.connection.vertex_buffer.slice(..));
-            rpass.set_index_buffer(
-                self.connection.index_buffer.slice(..),
-                wgpu::IndexFormat::Uint16,
+        let restart = self
+            .connection
+            .needs_restart(&viewport, &self.scene, self.settings.aovs);
+        if restart {
+            self.progress.restart();
+            self.counter.restart();
+        }
+        let counts = self.counter.read(&self.device);
+
+        // once the budget is used up the render is final, so only the post processing runs,
+        // though tiles and frames always render to their own sample count
+        let traced = restart
+            || self.tiled_render.is_some()
+            || self.sequence_render.is_some()
+            || !self.progress.is_finished(&self.settings.budget);
+        if traced {
+            self.connection.update_buffers(
+                &self.queue,
+                &viewport,
+                &self.scene,
+                self.settings.aovs,
+                &self.settings.adaptive,
             );
 
-            rpass.draw_indexed(0..(Connection::INDICES_NUM as u32), 0, 0..1);
+            if let Some(timer) = &self.timer {
+                timer.write(&mut encoder, Timestamp::TraceStart);
+            }
+            self.tracer
+                .encode(&mut encoder, &self.connection, &self.render_target);
+            if let Some(timer) = &self.timer {
+                timer.write(&mut encoder, Timestamp::TraceEnd);
+            }
+
+            self.progress.add_pass();
+        } else {
+            self.progress.pause();
         }
 
-        encoder.copy_texture_to_texture(
-            self.render_target.render_texture.as_image_copy(),
-            self.previous_render_texture.as_image_copy(),
-            wgpu::Extent3d {
-                width: self.render_target.size.0,
-                height: self.render_target.size.1,
-                depth_or_array_layers: 1,
-            },
-        );
+        self.encode_post_processing(&mut encoder);
+
+        if traced {
+            self.counter
+                .encode(&self.device, &mut encoder, &self.render_target);
+        }
+
+        if let Some(timer) = &mut self.timer {
+            timer.resolve(&mut encoder, traced);
+        }
 
         self.queue.submit(Some(encoder.finish()));
 
+        if let Some(timer) = &mut self.timer {
+            timer.map();
+        }
+        self.counter.map();
+        self.progress.statistics.record(counts, timings);
+
+        if self.tiled_render.is_some() {
+            self.add_tile();
+        } else if self.sequence_render.is_some() {
+            self.add_frame();
+        } else if self.settings.request == Some(Request::Export) {
+            self.settings.request = None;
+            self.export();
+        } else if self.settings.request == Some(Request::Reference) {
+            self.settings.request = None;
+            self.measure_convergence(true);
+        } else if traced
+            && self
+                .progress
+                .convergence
+                .should_measure(self.progress.passes)
+        {
+            self.measure_convergence(false);
+        }
+
         self.render_target
             .update(&self.device, &mut self.egui_renderer);
     }
 
+    /// Record denoising the render if enabled, then displaying it.
+    fn encode_post_processing(&self, encoder: &mut wgpu::CommandEncoder) {
+        let output = if self.settings.denoise.enabled {
+            self.denoiser.encode(
+                &self.queue,
+                encoder,
+                &self.settings.denoise,
+                self.connection.frame_data.progressive_count,
+            )
+        } else {
+            &self.render_target.render_view
+        };
+
+        if let Some(timer) = &self.timer {
+            timer.write(encoder, Timestamp::DisplayStart);
+        }
+        self.display.encode(
+            &self.device,
+            &self.queue,
+            encoder,
+            output,
+            &self.render_target,
+            &self.settings.display,
+        );
+        if let Some(timer) = &self.timer {
+            timer.write(encoder, Timestamp::DisplayEnd);
+        }
+    }
+
+    /// Start or cancel a tiled render if the UI asked to,
+    /// returning the part of the image to render this frame.
+    fn update_tiled_render(&mut self) -> Viewport {
+        match self.settings.request {
+            Some(Request::TiledRender) if self.sequence_render.is_none() => {
+                self.settings.request = None;
+                match TiledRender::new(
+                    &self.settings.tiles,
+                    RenderTarget::max_size(&self.device.limits()),
+                ) {
+                    Ok(tiled_render) => {
+                        println!("Rendering {} tiles", tiled_render.len());
+                        self.tiled_render = Some(tiled_render);
+                    }
+                    Err(error) => {
+                        eprintln!("Starting the tiled render failed with error: {error:#}");
+                    }
+                }
+            }
+            Some(Request::CancelTiledRender) => {
+                self.settings.request = None;
+                self.tiled_render = None;
+            }
+            _ => {}
+        }
+
+        self.progress.tiles = self
+            .tiled_render
+            .as_ref()
+            .map(|tiled_render| (tiled_render.current, tiled_render.len()));
+
+        self.tiled_render.as_ref().map_or_else(
+            || Viewport::full(self.render_target.requested_size),
+            TiledRender::viewport,
+        )
+    }
+
+    /// Start or cancel an animation render if the UI asked to,
+    /// and move the scene to the time of the frame being rendered.
+    fn update_sequence_render(&mut self) {
+        match self.settings.request {
+            Some(Request::RenderSequence) if self.tiled_render.is_none() => {
+                self.settings.request = None;
+                match SequenceRender::new(&self.settings.sequence) {
+                    Ok(sequence_render) => self.sequence_render = Some(sequence_render),
+                    Err(error) => eprintln!("Rendering the animation failed with error: {error:#}"),
+                }
+            }
+            Some(Request::CancelSequence) => {
+                self.settings.request = None;
+                self.sequence_render = None;
+            }
+            _ => {}
+        }
+
+        self.progress.frames = self.sequence_render.as_ref().map(SequenceRender::progress);
+
+        if let Some(sequence_render) = &self.sequence_render {
+            self.scene.animation.playing = false;
+            self.scene.animation.time = sequence_render.time();
+            self.scene.animate(0.);
+        }
+    }
+
+    /// Save the current frame once it has all its passes,
+    /// finishing after the last frame.
+    fn add_frame(&mut self) {
+        puffin::profile_function!();
+
+        let Some(sequence_render) = &mut self.sequence_render else {
+            return;
+        };
+        if !sequence_render.is_frame_finished(self.progress.passes) {
+            return;
+        }
+
+        let image = match sequence_render.format() {
+            FrameFormat::Exr => Aov::Beauty
+                .read(&self.device, &self.queue, &self.render_target)
+                .map(image::DynamicImage::ImageRgb32F),
+            FrameFormat::Png => read_display(&self.device, &self.queue, &self.render_target)
+                .map(image::DynamicImage::ImageRgba8),
+        };
+
+        match image.and_then(|image| sequence_render.save_frame(&image)) {
+            Ok(path) => println!("Saved {}", path.display()),
+            Err(error) => {
+                eprintln!("Rendering the animation failed with error: {error:#}");
+                self.sequence_render = None;
+                return;
+            }
+        }
+
+        if sequence_render.is_finished() {
+            println!("Finished rendering the animation");
+            self.sequence_render = None;
+        }
+    }
+
+    /// Whether the app has finished what it was started to do from the command line.
+    #[must_use]
+    pub const fn should_exit(&self) -> bool {
+        self.exit_after_sequence && self.sequence_render.is_none()
+    }
+
+    /// Stitch the current tile into the tiled render once it has all its passes,
+    /// saving the image after the last tile.
+    fn add_tile(&mut self) {
+        puffin::profile_function!();
+
+        let Some(tiled_render) = &mut self.tiled_render else {
+            return;
+        };
+        if !tiled_render.is_tile_finished(self.progress.passes) {
+            return;
+        }
+
+        let tile = match Aov::Beauty.read(&self.device, &self.queue, &self.render_target) {
+            Ok(tile) => tile,
+            Err(error) => {
+                eprintln!("Reading the tile failed with error: {error:#}");
+                self.tiled_render = None;
+                return;
+            }
+        };
+
+        if tiled_render.add_tile(&tile) {
+            match tiled_render.save() {
+                Ok(path) => println!("Saved the tiled render to {}", path.display()),
+                Err(error) => eprintln!("Saving the tiled render failed with error: {error:#}"),
+            }
+            self.tiled_render = None;
+        }
+    }
+
+    /// Export the beauty render and the available AOVs.
+    fn export(&self) {
+        puffin::profile_function!();
+
+        let aovs = Aov::ALL
+            .into_iter()
+            .filter(|aov| self.settings.aovs || !aov.is_optional());
+
+        match export_aovs(
+            &self.device,
+            &self.queue,
+            &self.render_target,
+            Path::new(&self.settings.export_path),
+            aovs,
+        ) {
+            Ok(paths) => {
+                for path in paths {
+                    println!("Exported {}", path.display());
+                }
+            }
+            Err(error) => eprintln!("Export failed with error: {error:#}"),
+        }
+    }
+
+    /// Measure the beauty render's error,
+    /// or use it as the reference to measure against.
+    fn measure_convergence(&mut self, is_reference: bool) {
+        puffin::profile_function!();
+
+        match Aov::Beauty.read(&self.device, &self.queue, &self.render_target) {
+            Ok(image) if is_reference => {
+                self.progress.convergence.set_reference(image);
+            }
+            Ok(image) => {
+                self.progress
+                    .convergence
+                    .measure(self.scene.sampler, self.progress.passes, &image);
+            }
+            Err(error) => eprintln!("Reading the render failed with error: {error:#}"),
+        }
+    }
+
     fn render_ui(&mut self) -> Result<()> {
         puffin::profile_function!();
 
@@ -274,8 +571,9 @@ impl App {
         self.ui.update(
             &self.egui_context,
             &mut self.render_target,
-            &self.device,
             &mut self.scene,
+            &mut self.settings,
+            &self.progress,
         )?;
 
         // End the UI frame. We could now handle the output and draw the UI with the backend.
@@ -360,7 +658,7 @@ impl App {
     /// # Errors
     ///
     /// An issue with the winit event loop such as an OS issue.
-    pub fn run() -> Result<()> {
+    pub fn run(args: &Args) -> Result<()> {
         let initial_window_size = (1920u32, 1080u32);
         let initial_render_size = (1000u32, 900u32);
 
@@ -378,9 +676,23 @@ impl App {
                 .build(&event_loop)?,
         );
 
+        let mut scene = match &args.scene_file {
+            Some(path) => Scene::load(path)?,
+            None => args.scene.scene(),
+        };
+        if let Some(seed) = args.seed {
+            scene.seed = seed;
+        }
+
         let mut app = pollster::block_on(Initial::new(window).gpu_setup())?
             .ui_setup()?
-            .renderer_setup(initial_render_size, Scene::random_spheres_default_config())?;
+            .renderer_setup(initial_render_size, scene, !args.no_compute)?;
+
+        app.settings.sequence = args.sequence.clone();
+        if args.render_sequence {
+            app.settings.request = Some(Request::RenderSequence);
+            app.exit_after_sequence = true;
+        }
 
         event_loop.run(|event, window_target| {
             window_target.set_control_flow(ControlFlow::Poll);
@@ -393,6 +705,10 @@ impl App {
                             Ok(()) => (),
                             Err(error) => eprintln!("Render failed with error: {error}"),
                         }
+
+                        if app.should_exit() {
+                            window_target.exit();
+                        }
                     }
                     egui_winit::winit::event::WindowEvent::Resized(size) => {
                         app.surface_config.width = size.width;
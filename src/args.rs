@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::ray_tracer::ScenePreset;
+use crate::sequence::{FrameFormat, SequenceSettings};
+
+/// The options given on the command line.
+pub struct Args {
+    /// The scene to open with.
+    pub scene: ScenePreset,
+    /// Overrides the scene's random seed.
+    pub seed: Option<u32>,
+    /// Always use the fragment tracer, even if the compute tracer is supported.
+    pub no_compute: bool,
+    /// A saved scene to open instead of the preset.
+    pub scene_file: Option<PathBuf>,
+    /// How the animation is rendered, if `render_sequence` is set.
+    pub sequence: SequenceSettings,
+    /// Render the animation to numbered images, then exit.
+    pub render_sequence: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            scene: ScenePreset::RandomSpheres,
+            seed: None,
+            no_compute: false,
+            scene_file: None,
+            sequence: SequenceSettings::default(),
+            render_sequence: false,
+        }
+    }
+}
+
+impl Args {
+    /// Parse the arguments the program was started with.
+    ///
+    /// # Errors
+    ///
+    /// If an argument is unknown or is missing its value.
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    /// Parse the given arguments, not including the program name.
+    ///
+    /// # Errors
+    ///
+    /// If an argument is unknown or is missing its value.
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scene" => {
+                    let id = args.next().context("--scene needs a scene name")?;
+                    parsed.scene = ScenePreset::from_id(&id).ok_or_else(|| {
+                        anyhow!(
+                            "Unknown scene '{id}', expected one of: {}",
+                            Self::scene_ids()
+                        )
+                    })?;
+                }
+                "--seed" => parsed.seed = Some(Self::value(&mut args, "--seed")?),
+                "--no-compute" => parsed.no_compute = true,
+                "--open" => {
+                    let path = args.next().context("--open needs a path")?;
+                    parsed.scene_file = Some(PathBuf::from(path));
+                }
+                "--frames" => {
+                    let range = args.next().context("--frames needs a range")?;
+                    let (first, last) = range
+                        .split_once('-')
+                        .and_then(|(first, last)| Some((first.parse().ok()?, last.parse().ok()?)))
+                        .filter(|&(first, last)| 0 < first && first <= last)
+                        .with_context(|| format!("Invalid frame range '{range}'"))?;
+                    parsed.sequence.first_frame = first;
+                    parsed.sequence.last_frame = last;
+                    parsed.render_sequence = true;
+                }
+                "--fps" => parsed.sequence.frame_rate = Self::value(&mut args, "--fps")?,
+                "--passes" => parsed.sequence.passes = Self::value(&mut args, "--passes")?,
+                "--output" => {
+                    parsed.sequence.directory = args.next().context("--output needs a folder")?;
+                }
+                "--format" => {
+                    let format = args.next().context("--format needs exr or png")?;
+                    parsed.sequence.format =
+                        FrameFormat::from_extension(&format).with_context(|| {
+                            format!("Unknown format '{format}', expected exr or png")
+                        })?;
+                }
+                "--help" | "-h" => {
+                    println!("{}", Self::help());
+                    std::process::exit(0);
+                }
+                arg => bail!("Unknown argument '{arg}'\n\n{}", Self::help()),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Parse the value following `flag`.
+    fn value<T: FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T> {
+        let value = args
+            .next()
+            .with_context(|| format!("{flag} needs a value"))?;
+        value
+            .parse()
+            .map_err(|_| anyhow!("Invalid value '{value}' for {flag}"))
+    }
+
+    fn scene_ids() -> String {
+        ScenePreset::ALL
+            .iter()
+            .map(|preset| preset.id())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn help() -> String {
+        format!(
+            "Usage: rs_ray_tracing_v3 [OPTIONS]\n\
+            \n\
+            Options:\n  \
+              --scene <NAME>  The scene to open with, one of: {}\n  \
+              --seed <N>      The seed for the renderer's random numbers\n  \
+              --no-compute    Trace with a fragment shader even if compute is supported\n  \
+              --open <PATH>   Open a saved scene instead of a built in one\n  \
+              -h, --help      Print this help\n\
+            \n\
+            Animation:\n  \
+              --frames <A-B>  Render frames A to B of the animation, then exit\n  \
+              --fps <N>       Frames per second of animation [default: 24]\n  \
+              --passes <N>    Passes over each frame, its samples per pixel unless sampling adaptively [default: 64]\n  \
+              --output <DIR>  The folder frames are saved in [default: ./frames]\n  \
+              --format <EXT>  exr or png [default: exr]",
+            Self::scene_ids()
+        )
+    }
+}
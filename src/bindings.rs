@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+
+use egui::{InputState, Key};
+use serde::{Deserialize, Serialize};
+
+/// Something the camera can be told to do.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum Action {
+    /// Move the way the camera faces.
+    MoveForward,
+    /// Move away from the way the camera faces.
+    MoveBackward,
+    /// Move to the camera's left.
+    MoveLeft,
+    /// Move to the camera's right.
+    MoveRight,
+    /// Move the way the camera's up is.
+    MoveUp,
+    /// Move away from the way the camera's up is.
+    MoveDown,
+    /// Pitch the camera up.
+    LookUp,
+    /// Pitch the camera down.
+    LookDown,
+    /// Turn the camera left.
+    LookLeft,
+    /// Turn the camera right.
+    LookRight,
+    /// Roll the camera to the left.
+    RollLeft,
+    /// Roll the camera to the right.
+    RollRight,
+    /// Move faster while held.
+    SpeedBoost,
+    /// Move slower while held.
+    SlowDown,
+}
+
+impl Action {
+    /// Every action, in the order they are shown in the UI.
+    pub const ALL: [Self; 14] = [
+        Self::MoveForward,
+        Self::MoveBackward,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::LookUp,
+        Self::LookDown,
+        Self::LookLeft,
+        Self::LookRight,
+        Self::RollLeft,
+        Self::RollRight,
+        Self::SpeedBoost,
+        Self::SlowDown,
+    ];
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::MoveForward => "Move forward",
+            Self::MoveBackward => "Move backward",
+            Self::MoveLeft => "Move left",
+            Self::MoveRight => "Move right",
+            Self::MoveUp => "Move up",
+            Self::MoveDown => "Move down",
+            Self::LookUp => "Look up",
+            Self::LookDown => "Look down",
+            Self::LookLeft => "Look left",
+            Self::LookRight => "Look right",
+            Self::RollLeft => "Roll left",
+            Self::RollRight => "Roll right",
+            Self::SpeedBoost => "Speed boost",
+            Self::SlowDown => "Slow down",
+        }
+    }
+
+    /// What the action is bound to before being rebound.
+    #[must_use]
+    pub const fn default_binding(self) -> Binding {
+        match self {
+            Self::MoveForward => Binding::Key(Key::W),
+            Self::MoveBackward => Binding::Key(Key::S),
+            Self::MoveLeft => Binding::Key(Key::A),
+            Self::MoveRight => Binding::Key(Key::D),
+            Self::MoveUp => Binding::Key(Key::E),
+            Self::MoveDown => Binding::Key(Key::Q),
+            Self::LookUp => Binding::Key(Key::ArrowUp),
+            Self::LookDown => Binding::Key(Key::ArrowDown),
+            Self::LookLeft => Binding::Key(Key::ArrowLeft),
+            Self::LookRight => Binding::Key(Key::ArrowRight),
+            Self::RollLeft => Binding::Key(Key::Z),
+            Self::RollRight => Binding::Key(Key::C),
+            Self::SpeedBoost => Binding::Shift,
+            Self::SlowDown => Binding::Ctrl,
+        }
+    }
+}
+
+/// A key or modifier an action is bound to.
+///
+/// Saved by name, like `"W"` or `"Shift"`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Binding {
+    /// A key, held or pressed.
+    Key(Key),
+    /// Either shift key.
+    Shift,
+    /// Either ctrl key.
+    Ctrl,
+    /// Either alt key.
+    Alt,
+}
+
+impl Binding {
+    /// The name shown in the UI and saved.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Key(key) => key.name(),
+            Self::Shift => "Shift",
+            Self::Ctrl => "Ctrl",
+            Self::Alt => "Alt",
+        }
+    }
+
+    /// Whether the key or modifier is held.
+    #[must_use]
+    pub fn is_down(self, input: &InputState) -> bool {
+        match self {
+            Self::Key(key) => input.key_down(key),
+            Self::Shift => input.modifiers.shift,
+            Self::Ctrl => input.modifiers.ctrl,
+            Self::Alt => input.modifiers.alt,
+        }
+    }
+
+    /// The key pressed or modifier held this frame, to bind to an action.
+    #[must_use]
+    pub fn pressed(input: &InputState) -> Option<Self> {
+        let key = input.events.iter().find_map(|event| match event {
+            egui::Event::Key {
+                key, pressed: true, ..
+            } => Some(*key),
+            _ => None,
+        });
+
+        let modifier = if input.modifiers.shift {
+            Some(Self::Shift)
+        } else if input.modifiers.ctrl {
+            Some(Self::Ctrl)
+        } else if input.modifiers.alt {
+            Some(Self::Alt)
+        } else {
+            None
+        };
+
+        key.map(Self::Key).or(modifier)
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.as_str() {
+            "Shift" => Ok(Self::Shift),
+            "Ctrl" => Ok(Self::Ctrl),
+            "Alt" => Ok(Self::Alt),
+            _ => Key::from_name(&name)
+                .map(Self::Key)
+                .ok_or_else(|| format!("Unknown key {name}")),
+        }
+    }
+}
+
+impl From<Binding> for String {
+    fn from(binding: Binding) -> Self {
+        binding.name().to_string()
+    }
+}
+
+/// Which key or modifier each action is bound to.
+///
+/// Actions missing from the saved map keep their default binding.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<Action, Binding>", into = "BTreeMap<Action, Binding>")]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Binding>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self::from(BTreeMap::new())
+    }
+}
+
+impl From<BTreeMap<Action, Binding>> for InputMap {
+    fn from(mut bindings: BTreeMap<Action, Binding>) -> Self {
+        for action in Action::ALL {
+            bindings
+                .entry(action)
+                .or_insert_with(|| action.default_binding());
+        }
+
+        Self { bindings }
+    }
+}
+
+impl From<InputMap> for BTreeMap<Action, Binding> {
+    fn from(map: InputMap) -> Self {
+        map.bindings
+    }
+}
+
+impl InputMap {
+    /// What the action is bound to.
+    #[must_use]
+    pub fn binding(&self, action: Action) -> Binding {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_binding())
+    }
+
+    /// Bind the action to a key or modifier, which may also be bound to other actions.
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        self.bindings.insert(action, binding);
+    }
+
+    /// Whether the action's key or modifier is held.
+    #[must_use]
+    pub fn is_down(&self, input: &InputState, action: Action) -> bool {
+        self.binding(action).is_down(input)
+    }
+
+    /// The other actions bound to the same key or modifier as this one.
+    pub fn conflicts(&self, action: Action) -> impl Iterator<Item = Action> + '_ {
+        let binding = self.binding(action);
+        self.bindings
+            .iter()
+            .filter(move |(other, other_binding)| **other != action && **other_binding == binding)
+            .map(|(other, _)| *other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_map_round_trips() -> serde_json::Result<()> {
+        let mut map = InputMap::default();
+        map.bind(Action::MoveForward, Binding::Key(Key::I));
+        map.bind(Action::SpeedBoost, Binding::Alt);
+
+        let json = serde_json::to_string(&map)?;
+        let loaded: InputMap = serde_json::from_str(&json)?;
+
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.binding(Action::MoveForward), Binding::Key(Key::I));
+        assert_eq!(loaded.binding(Action::SpeedBoost), Binding::Alt);
+        Ok(())
+    }
+
+    #[test]
+    fn bindings_are_saved_by_name() -> serde_json::Result<()> {
+        assert_eq!(serde_json::to_string(&Binding::Key(Key::W))?, "\"W\"");
+        assert_eq!(serde_json::to_string(&Binding::Shift)?, "\"Shift\"");
+        Ok(())
+    }
+
+    #[test]
+    fn missing_actions_keep_their_defaults() -> serde_json::Result<()> {
+        let map: InputMap = serde_json::from_str(r#"{ "MoveForward": "I" }"#)?;
+
+        assert_eq!(map.binding(Action::MoveForward), Binding::Key(Key::I));
+        for action in Action::ALL.into_iter().skip(1) {
+            assert_eq!(map.binding(action), action.default_binding());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert_eq!(
+            Binding::try_from("NotAKey".to_string()),
+            Err("Unknown key NotAKey".to_string())
+        );
+        assert!(serde_json::from_str::<InputMap>(r#"{ "MoveForward": "NotAKey" }"#).is_err());
+    }
+
+    #[test]
+    fn names_parse_back() {
+        for binding in [
+            Binding::Key(Key::ArrowUp),
+            Binding::Shift,
+            Binding::Ctrl,
+            Binding::Alt,
+        ] {
+            assert_eq!(Binding::try_from(String::from(binding)), Ok(binding));
+        }
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let map = InputMap::default();
+
+        for action in Action::ALL {
+            assert_eq!(map.conflicts(action).count(), 0);
+        }
+    }
+
+    #[test]
+    fn conflicts_are_the_other_actions_on_the_same_binding() {
+        let mut map = InputMap::default();
+        map.bind(Action::MoveUp, Binding::Key(Key::W));
+        map.bind(Action::RollLeft, Binding::Key(Key::W));
+
+        assert_eq!(
+            map.conflicts(Action::MoveForward).collect::<Vec<_>>(),
+            vec![Action::MoveUp, Action::RollLeft]
+        );
+        assert_eq!(
+            map.conflicts(Action::MoveUp).collect::<Vec<_>>(),
+            vec![Action::MoveForward, Action::RollLeft]
+        );
+        assert_eq!(map.conflicts(Action::MoveDown).count(), 0);
+    }
+}
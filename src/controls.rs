@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::InputMap;
+
+/// How the camera responds to the keyboard and mouse,
+/// kept between runs in [`Controls::PATH`].
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Controls {
+    /// Radians turned per second with the arrow keys.
+    pub look_speed: f32,
+    /// Units moved per second.
+    pub move_speed: f32,
+    /// How much faster holding shift moves.
+    pub fast_multiplier: f32,
+    /// How much slower holding ctrl moves.
+    pub slow_multiplier: f32,
+    /// Radians turned per pixel dragged with the right mouse button.
+    pub mouse_sensitivity: f32,
+    /// Whether dragging up looks down.
+    pub invert_y: bool,
+    /// The keys bound to moving and looking around.
+    pub bindings: InputMap,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            look_speed: 1.5,
+            move_speed: 20.,
+            fast_multiplier: 6.,
+            slow_multiplier: 0.4,
+            mouse_sensitivity: 0.005,
+            invert_y: false,
+            bindings: InputMap::default(),
+        }
+    }
+}
+
+impl Controls {
+    /// Where the controls are saved, next to the scene.
+    pub const PATH: &'static str = "./controls.json";
+
+    /// The slowest and fastest movement speeds the scroll wheel reaches.
+    pub const MOVE_SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.01..=1000.;
+
+    /// Load the controls, or the defaults if they haven't been saved yet.
+    ///
+    /// # Errors
+    ///
+    /// If the file couldn't be read or isn't valid.
+    pub fn load() -> Result<Self> {
+        let path = Path::new(Self::PATH);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid controls {}", path.display()))
+    }
+
+    /// Save the controls to be loaded next time.
+    ///
+    /// # Errors
+    ///
+    /// If the file couldn't be written.
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::PATH, json).with_context(|| format!("Failed to write {}", Self::PATH))
+    }
+
+    /// Change the movement speed by `amount` scrolled pixels.
+    pub fn scroll_move_speed(&mut self, amount: f32) {
+        self.move_speed = (self.move_speed * (amount * 0.002).exp()).clamp(
+            *Self::MOVE_SPEED_RANGE.start(),
+            *Self::MOVE_SPEED_RANGE.end(),
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use crate::ray_tracer::Sampler;
+
+/// Measures how quickly renders converge to a reference image,
+/// to compare the samplers.
+#[derive(Default)]
+pub struct Convergence {
+    reference: Option<image::Rgb32FImage>,
+    /// The error at each power of two samples per pixel, for each sampler measured.
+    pub curves: Vec<(Sampler, Vec<(u32, f32)>)>,
+}
+
+impl Convergence {
+    /// Whether there is a reference to measure against.
+    #[must_use]
+    pub const fn has_reference(&self) -> bool {
+        self.reference.is_some()
+    }
+
+    /// Measure against `reference` from now on, forgetting the previous measurements.
+    pub fn set_reference(&mut self, reference: image::Rgb32FImage) {
+        self.reference = Some(reference);
+        self.curves.clear();
+    }
+
+    /// Whether the render should be measured once it reaches `samples`.
+    #[must_use]
+    pub const fn should_measure(&self, samples: u32) -> bool {
+        self.has_reference() && samples.is_power_of_two()
+    }
+
+    /// Record the error of `image`, rendered with `spp` samples per pixel.
+    ///
+    /// The first sample starts a new curve for the sampler.
+    /// Nothing is recorded if the image isn't the same size as the reference.
+    pub fn measure(&mut self, sampler: Sampler, spp: u32, image: &image::Rgb32FImage) {
+        puffin::profile_function!();
+
+        let Some(reference) = &self.reference else {
+            return;
+        };
+        if reference.dimensions() != image.dimensions() {
+            return;
+        }
+
+        let squared_error = reference
+            .as_raw()
+            .iter()
+            .zip(image.as_raw())
+            .map(|(a, b)| f64::from(a - b).powi(2))
+            .sum::<f64>();
+        let rmse = (squared_error / reference.as_raw().len() as f64).sqrt() as f32;
+
+        if spp == 1 {
+            self.curves
+                .retain(|(curve_sampler, _)| *curve_sampler != sampler);
+            self.curves.push((sampler, Vec::new()));
+        }
+
+        if let Some((_, points)) = self
+            .curves
+            .iter_mut()
+            .find(|(curve_sampler, _)| *curve_sampler == sampler)
+        {
+            points.push((spp, rmse));
+        }
+    }
+}
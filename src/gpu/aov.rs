@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::readback::{bytes_to_f32s, read_buffer, read_texture};
+use super::RenderTarget;
+
+/// A render output which can be displayed and exported.
+///
+/// Other than the beauty render, these are all gathered at the first hit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Aov {
+    /// The full render.
+    Beauty,
+    /// The colour of the material.
+    Albedo,
+    /// The shading normal.
+    Normal,
+    /// The distance along the camera's view axis.
+    Depth,
+    /// The index of the object in the scene, or -1 if nothing was hit.
+    ObjectIndex,
+    /// Light emitted by the object, or the environment if nothing was hit.
+    Emission,
+    /// Light which bounced once before reaching the camera.
+    Direct,
+    /// Light which bounced more than once before reaching the camera.
+    Indirect,
+    /// How many samples each pixel has taken,
+    /// displayed as a heatmap of the samples taken this frame.
+    Samples,
+}
+
+impl Aov {
+    /// Every AOV, in the order they are numbered in `display.hlsl`.
+    pub const ALL: [Self; 9] = [
+        Self::Beauty,
+        Self::Albedo,
+        Self::Normal,
+        Self::Depth,
+        Self::ObjectIndex,
+        Self::Emission,
+        Self::Direct,
+        Self::Indirect,
+        Self::Samples,
+    ];
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Beauty => "Beauty",
+            Self::Albedo => "Albedo",
+            Self::Normal => "Normal",
+            Self::Depth => "Depth",
+            Self::ObjectIndex => "Object index",
+            Self::Emission => "Emission",
+            Self::Direct => "Direct",
+            Self::Indirect => "Indirect",
+            Self::Samples => "Samples",
+        }
+    }
+
+    /// The name used in exported file names.
+    #[must_use]
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Beauty => "beauty",
+            Self::Albedo => "albedo",
+            Self::Normal => "normal",
+            Self::Depth => "depth",
+            Self::ObjectIndex => "object_index",
+            Self::Emission => "emission",
+            Self::Direct => "direct",
+            Self::Indirect => "indirect",
+            Self::Samples => "samples",
+        }
+    }
+
+    /// The number passed to the display shader.
+    #[must_use]
+    pub const fn index(self) -> u32 {
+        self as u32
+    }
+
+    /// Whether this is stored in the optional AOV buffer,
+    /// so is only available when AOVs are enabled.
+    #[must_use]
+    pub const fn is_optional(self) -> bool {
+        matches!(
+            self,
+            Self::ObjectIndex | Self::Emission | Self::Direct | Self::Indirect
+        )
+    }
+
+    /// Read the AOV back from the GPU.
+    ///
+    /// # Errors
+    ///
+    /// If the GPU memory couldn't be read.
+    pub fn read(
+        self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_target: &RenderTarget,
+    ) -> Result<image::Rgb32FImage> {
+        puffin::profile_function!();
+
+        let (width, height) = render_target.size;
+
+        // (data, floats per pixel, offset in floats of the value)
+        let (data, stride, offset) = match self {
+            Self::Beauty => (
+                read_texture(
+                    device,
+                    queue,
+                    &render_target.render_texture,
+                    (width, height),
+                    16,
+                )?,
+                4,
+                0,
+            ),
+            Self::Samples => (
+                read_texture(
+                    device,
+                    queue,
+                    &render_target.stats_texture,
+                    (width, height),
+                    16,
+                )?,
+                4,
+                2,
+            ),
+            Self::Albedo | Self::Normal | Self::Depth => (
+                read_buffer(device, queue, &render_target.gbuffer)?,
+                RenderTarget::GBUFFER_STRIDE as usize / 4,
+                match self {
+                    Self::Albedo => 0,
+                    Self::Depth => 3,
+                    _ => 4,
+                },
+            ),
+            Self::ObjectIndex | Self::Emission | Self::Direct | Self::Indirect => (
+                read_buffer(device, queue, &render_target.aovs)?,
+                RenderTarget::AOVS_STRIDE as usize / 4,
+                match self {
+                    Self::Emission => 0,
+                    Self::ObjectIndex => 3,
+                    Self::Direct => 4,
+                    _ => 8,
+                },
+            ),
+        };
+
+        let image = if self == Self::ObjectIndex {
+            // Stored as an int, not a float
+            let indices = data
+                .chunks_exact(4)
+                .map(|chunk| i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f32)
+                .collect::<Vec<_>>();
+
+            image::Rgb32FImage::from_fn(width, height, |x, y| {
+                let value = indices[(y * width + x) as usize * stride + offset];
+                image::Rgb([value; 3])
+            })
+        } else {
+            let floats = bytes_to_f32s(&data);
+            let is_scalar = matches!(self, Self::Depth | Self::Samples);
+
+            image::Rgb32FImage::from_fn(width, height, |x, y| {
+                let i = (y * width + x) as usize * stride + offset;
+                if is_scalar {
+                    image::Rgb([floats[i]; 3])
+                } else {
+                    image::Rgb([floats[i], floats[i + 1], floats[i + 2]])
+                }
+            })
+        };
+
+        Ok(image)
+    }
+}
+
+/// Export AOVs as EXR files named `{prefix}_{aov}.exr`,
+/// returning the paths written.
+///
+/// # Errors
+///
+/// If the GPU memory couldn't be read, or a file couldn't be written.
+pub fn export_aovs(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    render_target: &RenderTarget,
+    prefix: &Path,
+    aovs: impl Iterator<Item = Aov>,
+) -> Result<Vec<PathBuf>> {
+    puffin::profile_function!();
+
+    let file_name = prefix
+        .file_name()
+        .context("Export path needs a file name")?
+        .to_string_lossy()
+        .into_owned();
+
+    aovs.map(|aov| {
+        let path = prefix.with_file_name(format!("{file_name}_{}.exr", aov.id()));
+
+        aov.read(device, queue, render_target)?
+            .save(&path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(path)
+    })
+    .collect()
+}
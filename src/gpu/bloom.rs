@@ -0,0 +1,234 @@
+use crate::bytes::{bytes_concat, AsBytes};
+
+use super::fullscreen::{buffer_entry, draw_fullscreen, fullscreen_pipeline, texture_entry};
+use super::RenderTarget;
+
+/// The user facing settings of the bloom.
+#[derive(Clone, PartialEq)]
+pub struct BloomSettings {
+    /// Whether the bloom runs at all.
+    pub enabled: bool,
+    /// How bright a pixel has to be after exposure to glow.
+    pub threshold: f32,
+    /// How much of the glow is added to the image.
+    pub intensity: f32,
+    /// From 0 to 1, how much the widest blurs are favoured over the narrowest.
+    pub radius: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 1.,
+            intensity: 0.1,
+            radius: 0.7,
+        }
+    }
+}
+
+/// What a single pass does, as in `bloom.hlsl`.
+#[derive(Clone, Copy)]
+enum BloomPass {
+    /// Keep the parts of the render over the threshold, at half the size.
+    Threshold,
+    /// Halve the size of the level above.
+    Downsample,
+    /// Double the size of the level below, blending it into this level.
+    Upsample,
+}
+
+/// The parameters of a single pass, as passed to the shader.
+struct BloomParams {
+    pass: BloomPass,
+    threshold: f32,
+    radius: f32,
+    exposure: f32,
+}
+
+impl BloomParams {
+    const BUFFER_SIZE: usize = 16;
+}
+
+impl AsBytes<{ Self::BUFFER_SIZE }> for BloomParams {
+    fn as_bytes(&self) -> [u8; Self::BUFFER_SIZE] {
+        bytes_concat(
+            [
+                (self.pass as u32).to_le_bytes().as_slice(),
+                &self.threshold.to_le_bytes(),
+                &self.radius.to_le_bytes(),
+                &self.exposure.to_le_bytes(),
+            ]
+            .into_iter(),
+        )
+    }
+}
+
+/// Makes bright parts of the image glow,
+/// by blurring them down a chain of smaller and smaller textures then back up it.
+///
+/// Only the displayed image has bloom, the accumulated render is never modified.
+pub struct Bloom {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    /// One for each pass, downsampling then upsampling.
+    params: Vec<wgpu::Buffer>,
+    /// Each half the size of the last, starting at half the size of the render.
+    down: Vec<(wgpu::Texture, wgpu::TextureView)>,
+    /// The same sizes as `down`, without the smallest.
+    up: Vec<(wgpu::Texture, wgpu::TextureView)>,
+
+    pub size: (u32, u32),
+}
+
+impl Bloom {
+    /// How many times the render is halved in size.
+    const LEVELS: usize = 6;
+
+    fn create_textures(
+        device: &wgpu::Device,
+        size: (u32, u32),
+        levels: std::ops::Range<usize>,
+    ) -> Vec<(wgpu::Texture, wgpu::TextureView)> {
+        levels
+            .map(|level| {
+                RenderTarget::create_texture(
+                    device,
+                    (
+                        (size.0 >> (level + 1)).max(1),
+                        (size.1 >> (level + 1)).max(1),
+                    ),
+                    RenderTarget::RENDER_FORMAT,
+                    wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                )
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn new(device: &wgpu::Device, size: (u32, u32)) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                buffer_entry(2, wgpu::BufferBindingType::Uniform),
+            ],
+        });
+
+        let pipeline = fullscreen_pipeline(
+            device,
+            &bind_group_layout,
+            &super::bloom_shader(device),
+            RenderTarget::RENDER_FORMAT,
+        );
+
+        let params = (0..Self::LEVELS * 2 - 1)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: BloomParams::BUFFER_SIZE as u64,
+                    mapped_at_creation: false,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+            })
+            .collect();
+
+        Self {
+            pipeline,
+            bind_group_layout,
+
+            params,
+            down: Self::create_textures(device, size, 0..Self::LEVELS),
+            up: Self::create_textures(device, size, 0..Self::LEVELS - 1),
+
+            size,
+        }
+    }
+
+    /// Recreate the textures to match a resized render target.
+    pub fn resize(&mut self, device: &wgpu::Device, size: (u32, u32)) {
+        self.size = size;
+        self.down = Self::create_textures(device, size, 0..Self::LEVELS);
+        self.up = Self::create_textures(device, size, 0..Self::LEVELS - 1);
+    }
+
+    /// The glow, at half the size of the render.
+    #[must_use]
+    pub fn output(&self) -> &wgpu::TextureView {
+        &self.up[0].1
+    }
+
+    /// Record the passes which blur the bright parts of `source` into [`Bloom::output`],
+    /// where `exposure` is what `source` is multiplied by when displayed.
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        settings: &BloomSettings,
+        exposure: f32,
+    ) {
+        puffin::profile_function!();
+
+        let mut params = self.params.iter();
+        let mut pass = |pass: BloomPass,
+                        input: &wgpu::TextureView,
+                        coarse: &wgpu::TextureView,
+                        target: &wgpu::TextureView| {
+            let Some(buffer) = params.next() else {
+                return;
+            };
+            let bloom_params = BloomParams {
+                pass,
+                threshold: settings.threshold,
+                radius: settings.radius,
+                exposure,
+            };
+            queue.write_buffer(buffer, 0, bloom_params.as_bytes().as_slice());
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(coarse),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            draw_fullscreen(encoder, &self.pipeline, &bind_group, target);
+        };
+
+        // the coarse texture is only read when upsampling
+        pass(BloomPass::Threshold, source, source, &self.down[0].1);
+        for level in 1..Self::LEVELS {
+            let input = &self.down[level - 1].1;
+            pass(BloomPass::Downsample, input, input, &self.down[level].1);
+        }
+
+        for level in (0..Self::LEVELS - 1).rev() {
+            let coarse = self
+                .up
+                .get(level + 1)
+                .map_or(&self.down[Self::LEVELS - 1].1, |(_, view)| view);
+            pass(
+                BloomPass::Upsample,
+                &self.down[level].1,
+                coarse,
+                &self.up[level].1,
+            );
+        }
+    }
+}
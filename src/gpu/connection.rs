@@ -1,28 +1,50 @@
 use anyhow::{Context, Result};
 use image::{EncodableLayout, GenericImageView};
-use nalgebra::Vector2;
-use std::sync::Arc;
 
 use crate::{
     bytes::{bytes_concat, bytes_concat_owned, AsBytes},
-    ray_tracer::{Scene, Vec3},
+    ray_tracer::{Scene, Vec3, Viewport},
 };
 
-use super::RandomTexture;
+/// Settings for spending more samples on noisy pixels and none on converged ones.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AdaptiveSettings {
+    /// Whether pixels take a varying number of samples each frame.
+    pub enabled: bool,
+    /// A pixel stops being traced once the relative standard error
+    /// of its mean luminance falls below this.
+    pub threshold: f32,
+    /// The samples every pixel takes before its error is trusted.
+    pub min_samples: u32,
+    /// The most samples a pixel takes in one frame.
+    pub max_samples: u32,
+}
+
+impl Default for AdaptiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0.02,
+            min_samples: 16,
+            max_samples: 4,
+        }
+    }
+}
 
 pub struct FrameData {
-    pub jitter: Vector2<f32>,
     pub progressive_count: u32,
+    pub write_aovs: bool,
+    pub adaptive: AdaptiveSettings,
 }
 
 impl FrameData {
-    const BUFFER_SIZE: usize = 16;
-    const JITTER_STRENGTH: f32 = 0.99;
+    const BUFFER_SIZE: usize = 32;
 
-    pub const fn new(jitter: Vector2<f32>) -> Self {
+    pub fn new() -> Self {
         Self {
-            jitter,
             progressive_count: 0,
+            write_aovs: false,
+            adaptive: AdaptiveSettings::default(),
         }
     }
 }
@@ -31,8 +53,13 @@ impl AsBytes<{ Self::BUFFER_SIZE }> for FrameData {
     fn as_bytes(&self) -> [u8; Self::BUFFER_SIZE] {
         bytes_concat(
             [
-                &self.jitter.as_bytes(),
                 self.progressive_count.to_le_bytes().as_slice(),
+                &u32::from(self.write_aovs).to_le_bytes(),
+                &u32::from(self.adaptive.enabled).to_le_bytes(),
+                &self.adaptive.threshold.to_le_bytes(),
+                &self.adaptive.min_samples.to_le_bytes(),
+                &self.adaptive.max_samples.to_le_bytes(),
+                &[0u8; 8],
             ]
             .into_iter(),
         )
@@ -44,7 +71,7 @@ pub struct Connection {
     pub bind_group: wgpu::BindGroup,
 
     pub last_scene: Scene,
-    pub last_size: (u32, u32),
+    pub last_viewport: Viewport,
     pub frame_data: FrameData,
 
     pub objects: wgpu::Buffer,
@@ -73,7 +100,7 @@ impl Connection {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Storage { read_only: true },
                         has_dynamic_offset: false,
@@ -83,7 +110,7 @@ impl Connection {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Storage { read_only: true },
                         has_dynamic_offset: false,
@@ -93,7 +120,7 @@ impl Connection {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 2,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -103,13 +130,13 @@ impl Connection {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 3,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
                     count: None,
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 4,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Texture {
                         sample_type: wgpu::TextureSampleType::Float { filterable: false },
                         view_dimension: wgpu::TextureViewDimension::D2,
@@ -119,27 +146,7 @@ impl Connection {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 5,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 6,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 7,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -294,15 +301,9 @@ impl Connection {
         (vertex_buffer, index_buffer)
     }
 
-    pub fn new(
-        scene: &Scene,
-        device: &wgpu::Device,
-        queue: Arc<wgpu::Queue>,
-        render_view: &wgpu::TextureView,
-    ) -> Result<Self> {
+    pub fn new(scene: &Scene, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Self> {
         let sampler = Self::create_sampler(device);
-        let hdri_texture_view = Self::load_hdri(device, &queue)?;
-        let random_texture_view = RandomTexture::start(device, queue);
+        let hdri_texture_view = Self::load_hdri(device, queue)?;
 
         let [objects, lights, config, frame_data_buffer] = Self::create_buffers(device);
 
@@ -334,14 +335,6 @@ impl Connection {
                 },
                 wgpu::BindGroupEntry {
                     binding: 5,
-                    resource: wgpu::BindingResource::TextureView(render_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 6,
-                    resource: wgpu::BindingResource::TextureView(&random_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 7,
                     resource: frame_data_buffer.as_entire_binding(),
                 },
             ],
@@ -354,8 +347,8 @@ impl Connection {
             bind_group_layout,
 
             last_scene: scene.clone(),
-            last_size: (0, 0),
-            frame_data: FrameData::new(Vector2::zeros()),
+            last_viewport: Viewport::full((0, 0)),
+            frame_data: FrameData::new(),
 
             objects,
             lights,
@@ -367,37 +360,44 @@ impl Connection {
         })
     }
 
-    pub fn update_buffers(&mut self, queue: &wgpu::Queue, size: (u32, u32), scene: &Scene) {
+    /// Whether anything changed which invalidates the accumulated render.
+    #[must_use]
+    pub fn needs_restart(&self, viewport: &Viewport, scene: &Scene, write_aovs: bool) -> bool {
+        (scene != &self.last_scene)
+            || (viewport != &self.last_viewport)
+            || (write_aovs != self.frame_data.write_aovs)
+    }
+
+    pub fn update_buffers(
+        &mut self,
+        queue: &wgpu::Queue,
+        viewport: &Viewport,
+        scene: &Scene,
+        write_aovs: bool,
+        adaptive: &AdaptiveSettings,
+    ) {
         puffin::profile_function!();
 
-        if (scene != &self.last_scene) | (size != self.last_size) {
+        if self.needs_restart(viewport, scene, write_aovs) {
             puffin::profile_scope!("serialize_scene");
 
-            let (object_bytes, light_bytes, config_bytes) = scene.as_bytes(size.0, size.1);
+            let (object_bytes, light_bytes, config_bytes) = scene.as_bytes(viewport);
 
             queue.write_buffer(&self.objects, 0, object_bytes.as_slice());
             queue.write_buffer(&self.lights, 0, light_bytes.as_slice());
             queue.write_buffer(&self.config, 0, config_bytes.as_slice());
 
             self.last_scene = scene.clone();
-            self.last_size = size;
+            self.last_viewport = *viewport;
+            self.frame_data.write_aovs = write_aovs;
 
             self.frame_data.progressive_count = 0;
         } else {
             self.frame_data.progressive_count += 1;
         }
 
-        {
-            puffin::profile_scope!("jitter_gen");
-            self.frame_data.jitter.x = rand::random::<f32>().mul_add(
-                FrameData::JITTER_STRENGTH,
-                -(FrameData::JITTER_STRENGTH / 2.),
-            );
-            self.frame_data.jitter.y = rand::random::<f32>().mul_add(
-                FrameData::JITTER_STRENGTH,
-                -(FrameData::JITTER_STRENGTH / 2.),
-            );
-        }
+        // the stats are kept regardless, so changing these doesn't restart the render
+        self.frame_data.adaptive = adaptive.clone();
 
         {
             puffin::profile_scope!("serialize_frame_data");
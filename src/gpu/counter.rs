@@ -0,0 +1,262 @@
+use std::sync::{Arc, Mutex};
+
+use super::RenderTarget;
+
+/// The size of the counting shader's workgroups, as in `count.hlsl`.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// The samples and rays the whole render has taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SampleCounts {
+    /// The pixels in the render, to average the samples over.
+    pub pixels: u64,
+    pub samples: u64,
+    /// Every bounce of every path.
+    pub rays: u64,
+}
+
+impl SampleCounts {
+    /// The size in bytes of one row's counts, as `RowCount` in `count.hlsl`.
+    const ROW_SIZE: usize = 16;
+
+    /// Add up the counts of each row, as read back from the GPU.
+    fn from_rows(pixels: u64, bytes: &[u8]) -> Self {
+        let mut counts = Self {
+            pixels,
+            samples: 0,
+            rays: 0,
+        };
+
+        for row in bytes.chunks_exact(Self::ROW_SIZE) {
+            // each count is split into its low and high halves
+            let halves = row
+                .chunks_exact(std::mem::size_of::<u32>())
+                .map(|bytes| u64::from(u32::from_le_bytes(bytes.try_into().unwrap_or_default())))
+                .collect::<Vec<_>>();
+            counts.samples += halves[0] | (halves[1] << 32);
+            counts.rays += halves[2] | (halves[3] << 32);
+        }
+
+        counts
+    }
+
+    /// The mean samples taken by each pixel.
+    #[must_use]
+    pub fn samples_per_pixel(&self) -> f64 {
+        self.samples as f64 / self.pixels.max(1) as f64
+    }
+}
+
+/// Where the counter is in reading back the counts.
+#[derive(Clone, Copy)]
+enum ReadState {
+    Idle,
+    /// The count is recorded but not yet submitted.
+    Encoded,
+    /// Waiting for the readback buffer to be mapped.
+    Mapping,
+}
+
+/// Counts the samples and rays of the whole render,
+/// by summing each row of the render on the GPU and reading the sums back.
+///
+/// As with [`super::GpuTimer`] the counts are read back without blocking,
+/// so they arrive a few frames late.
+pub struct SampleCounter {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// One count for each row of the render.
+    rows_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// The size of the render being counted.
+    size: (u32, u32),
+    state: ReadState,
+    /// Set when the render restarts while counting,
+    /// so the counts of the old render are thrown away.
+    stale: bool,
+    /// Set once mapping the readback buffer finishes, to whether it succeeded.
+    mapped: Arc<Mutex<Option<bool>>>,
+}
+
+impl SampleCounter {
+    fn create_buffers(device: &wgpu::Device, size: (u32, u32)) -> [wgpu::Buffer; 2] {
+        let buffer_size = u64::from(size.1.max(1)) * SampleCounts::ROW_SIZE as u64;
+
+        [
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        ]
+        .map(|usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("sample counts"),
+                size: buffer_size,
+                mapped_at_creation: false,
+                usage,
+            })
+        })
+    }
+
+    #[must_use]
+    pub fn new(device: &wgpu::Device, size: (u32, u32)) -> Self {
+        let buffer_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                buffer_entry(1, true),
+                buffer_entry(2, false),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+            ..Default::default()
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            module: &super::count_shader(device),
+            entry_point: "cs_main",
+        });
+
+        let [rows_buffer, readback_buffer] = Self::create_buffers(device, size);
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            rows_buffer,
+            readback_buffer,
+            size,
+            state: ReadState::Idle,
+            stale: false,
+            mapped: Arc::default(),
+        }
+    }
+
+    /// Throw away the counts being read back, as they are of the render before it restarted.
+    pub const fn restart(&mut self) {
+        self.stale = !matches!(self.state, ReadState::Idle);
+    }
+
+    /// Record counting the render and copying the counts to be read back,
+    /// unless the last counts are still being read.
+    pub fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &RenderTarget,
+    ) {
+        puffin::profile_function!();
+
+        if !matches!(self.state, ReadState::Idle) {
+            return;
+        }
+
+        if self.size != render_target.size {
+            self.size = render_target.size;
+            [self.rows_buffer, self.readback_buffer] = Self::create_buffers(device, self.size);
+        }
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&render_target.stats_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: render_target.ray_counts.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.rows_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            cpass.set_pipeline(&self.pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.dispatch_workgroups(self.size.1.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+
+        encoder.copy_buffer_to_buffer(
+            &self.rows_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.readback_buffer.size(),
+        );
+        self.state = ReadState::Encoded;
+    }
+
+    /// Start reading back the counts, once the encoder counting them has been submitted.
+    pub fn map(&mut self) {
+        if !matches!(self.state, ReadState::Encoded) {
+            return;
+        }
+
+        let mapped = self.mapped.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if let Ok(mut mapped) = mapped.lock() {
+                    *mapped = Some(result.is_ok());
+                }
+            });
+        self.state = ReadState::Mapping;
+    }
+
+    /// The counts last read back, if they've arrived since last called.
+    pub fn read(&mut self, device: &wgpu::Device) -> Option<SampleCounts> {
+        if !matches!(self.state, ReadState::Mapping) {
+            return None;
+        }
+
+        device.poll(wgpu::Maintain::Poll);
+        let mapped = self.mapped.lock().ok()?.take();
+        match mapped {
+            Some(true) => {}
+            Some(false) => {
+                // try again with the next frame
+                self.state = ReadState::Idle;
+                return None;
+            }
+            None => return None,
+        }
+
+        let pixels = u64::from(self.size.0) * u64::from(self.size.1);
+        let counts =
+            SampleCounts::from_rows(pixels, &self.readback_buffer.slice(..).get_mapped_range());
+        self.readback_buffer.unmap();
+        self.state = ReadState::Idle;
+
+        if self.stale {
+            self.stale = false;
+            return None;
+        }
+
+        Some(counts)
+    }
+}
@@ -0,0 +1,251 @@
+use crate::bytes::{bytes_concat, AsBytes};
+
+use super::fullscreen::{buffer_entry, draw_fullscreen, fullscreen_pipeline, texture_entry};
+use super::RenderTarget;
+
+/// The user facing settings of the denoiser.
+#[derive(Clone, PartialEq)]
+pub struct DenoiseSettings {
+    /// Whether the denoiser runs at all.
+    pub enabled: bool,
+    /// How many times the filter is applied,
+    /// each time with double the spacing between samples.
+    pub iterations: u32,
+    /// How much colour differences are allowed to be blurred.
+    ///
+    /// This is scaled down as more samples are accumulated,
+    /// so converged renders are left mostly untouched.
+    pub colour_phi: f32,
+    /// How sharply differences in normal stop the blur.
+    pub normal_phi: f32,
+    /// How much relative differences in depth are allowed to be blurred.
+    pub depth_phi: f32,
+    /// How much albedo differences are allowed to be blurred.
+    pub albedo_phi: f32,
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            iterations: 4,
+            colour_phi: 1.,
+            normal_phi: 64.,
+            depth_phi: 0.1,
+            albedo_phi: 0.05,
+        }
+    }
+}
+
+/// The parameters of a single iteration, as passed to the shader.
+struct DenoiseParams {
+    step: u32,
+    colour_phi: f32,
+    normal_phi: f32,
+    depth_phi: f32,
+    albedo_phi: f32,
+    width: u32,
+    height: u32,
+}
+
+impl DenoiseParams {
+    const BUFFER_SIZE: usize = 32;
+}
+
+impl AsBytes<{ Self::BUFFER_SIZE }> for DenoiseParams {
+    fn as_bytes(&self) -> [u8; Self::BUFFER_SIZE] {
+        bytes_concat(
+            [
+                self.step.to_le_bytes().as_slice(),
+                &self.colour_phi.to_le_bytes(),
+                &self.normal_phi.to_le_bytes(),
+                &self.depth_phi.to_le_bytes(),
+                &self.albedo_phi.to_le_bytes(),
+                &self.width.to_le_bytes(),
+                &self.height.to_le_bytes(),
+            ]
+            .into_iter(),
+        )
+    }
+}
+
+/// An edge avoiding a-trous wavelet denoiser,
+/// guided by the first hit albedo, normal and depth in the gbuffer.
+///
+/// Each iteration ping-pongs between two textures,
+/// so the accumulated render itself is never modified.
+pub struct Denoiser {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    params: Vec<wgpu::Buffer>,
+    textures: [(wgpu::Texture, wgpu::TextureView); 2],
+    bind_groups: Vec<wgpu::BindGroup>,
+
+    pub size: (u32, u32),
+}
+
+impl Denoiser {
+    /// The most iterations the denoiser can be set to.
+    pub const MAX_ITERATIONS: u32 = 5;
+
+    fn create_textures(
+        device: &wgpu::Device,
+        size: (u32, u32),
+    ) -> [(wgpu::Texture, wgpu::TextureView); 2] {
+        [0, 1].map(|_| {
+            RenderTarget::create_texture(
+                device,
+                size,
+                RenderTarget::RENDER_FORMAT,
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            )
+        })
+    }
+
+    /// Each iteration reads the output of the last,
+    /// with the first reading the accumulated render.
+    fn create_bind_groups(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        params: &[wgpu::Buffer],
+        textures: &[(wgpu::Texture, wgpu::TextureView); 2],
+        render_target: &RenderTarget,
+    ) -> Vec<wgpu::BindGroup> {
+        params
+            .iter()
+            .enumerate()
+            .map(|(i, params)| {
+                let input = if i == 0 {
+                    &render_target.render_view
+                } else {
+                    &textures[(i - 1) % 2].1
+                };
+
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: None,
+                    layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(input),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: render_target.gbuffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: params.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn new(device: &wgpu::Device, render_target: &RenderTarget) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                texture_entry(0),
+                buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, wgpu::BufferBindingType::Uniform),
+            ],
+        });
+
+        let pipeline = fullscreen_pipeline(
+            device,
+            &bind_group_layout,
+            &super::atrous_shader(device),
+            RenderTarget::RENDER_FORMAT,
+        );
+
+        let params = (0..Self::MAX_ITERATIONS)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: DenoiseParams::BUFFER_SIZE as u64,
+                    mapped_at_creation: false,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let textures = Self::create_textures(device, render_target.size);
+        let bind_groups = Self::create_bind_groups(
+            device,
+            &bind_group_layout,
+            &params,
+            &textures,
+            render_target,
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+
+            params,
+            textures,
+            bind_groups,
+
+            size: render_target.size,
+        }
+    }
+
+    /// Recreate the textures to match a resized render target.
+    pub fn resize(&mut self, device: &wgpu::Device, render_target: &RenderTarget) {
+        self.size = render_target.size;
+        self.textures = Self::create_textures(device, self.size);
+        self.bind_groups = Self::create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            &self.params,
+            &self.textures,
+            render_target,
+        );
+    }
+
+    /// Record the denoising passes,
+    /// returning the view holding the denoised render.
+    ///
+    /// `progressive_count` is how many frames have been accumulated,
+    /// which the colour weight is scaled by.
+    pub fn encode(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        settings: &DenoiseSettings,
+        progressive_count: u32,
+    ) -> &wgpu::TextureView {
+        puffin::profile_function!();
+
+        let iterations = settings.iterations.clamp(1, Self::MAX_ITERATIONS) as usize;
+        // The variance of the render falls with each sample
+        let colour_phi = settings.colour_phi / (progressive_count + 1) as f32;
+
+        for i in 0..iterations {
+            let params = DenoiseParams {
+                step: 1 << i,
+                // Each iteration the remaining noise is smaller
+                colour_phi: colour_phi / (1 << i) as f32,
+                normal_phi: settings.normal_phi,
+                depth_phi: settings.depth_phi,
+                albedo_phi: settings.albedo_phi,
+                width: self.size.0,
+                height: self.size.1,
+            };
+            queue.write_buffer(&self.params[i], 0, params.as_bytes().as_slice());
+
+            draw_fullscreen(
+                encoder,
+                &self.pipeline,
+                &self.bind_groups[i],
+                &self.textures[i % 2].1,
+            );
+        }
+
+        &self.textures[(iterations - 1) % 2].1
+    }
+}
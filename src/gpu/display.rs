@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+
+use crate::bytes::{bytes_concat, AsBytes};
+
+use super::fullscreen::{buffer_entry, draw_fullscreen, fullscreen_pipeline, texture_entry};
+use super::readback::read_texture;
+use super::{Aov, Bloom, BloomSettings, RenderTarget};
+
+/// The user facing settings of what is displayed.
+#[derive(Clone, PartialEq)]
+pub struct DisplaySettings {
+    /// Which AOV is shown.
+    pub view: Aov,
+    /// The depth shown as black when viewing the depth AOV.
+    pub depth_range: f32,
+    /// What the beauty and lighting AOVs are multiplied by,
+    /// set by the scene's physical camera when it has one.
+    pub exposure: f32,
+    /// The glow added around bright parts of the beauty.
+    pub bloom: BloomSettings,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            view: Aov::Beauty,
+            depth_range: 100.,
+            exposure: 1.,
+            bloom: BloomSettings::default(),
+        }
+    }
+}
+
+/// The parameters of the display pass, as passed to the shader.
+struct DisplayParams {
+    view: Aov,
+    width: u32,
+    depth_range: f32,
+    exposure: f32,
+    bloom_intensity: f32,
+}
+
+impl DisplayParams {
+    const BUFFER_SIZE: usize = 32;
+}
+
+impl AsBytes<{ Self::BUFFER_SIZE }> for DisplayParams {
+    fn as_bytes(&self) -> [u8; Self::BUFFER_SIZE] {
+        bytes_concat(
+            [
+                self.view.index().to_le_bytes().as_slice(),
+                &self.width.to_le_bytes(),
+                &self.depth_range.to_le_bytes(),
+                &self.exposure.to_le_bytes(),
+                &self.bloom_intensity.to_le_bytes(),
+            ]
+            .into_iter(),
+        )
+    }
+}
+
+/// Converts the HDR render, or one of its AOVs, into the image shown in the UI.
+pub struct Display {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    params: wgpu::Buffer,
+    pub bloom: Bloom,
+}
+
+impl Display {
+    #[must_use]
+    pub fn new(device: &wgpu::Device, render_target: &RenderTarget) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                texture_entry(0),
+                buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: true }),
+                buffer_entry(3, wgpu::BufferBindingType::Uniform),
+                texture_entry(4),
+                texture_entry(5),
+            ],
+        });
+
+        let pipeline = fullscreen_pipeline(
+            device,
+            &bind_group_layout,
+            &super::display_shader(device),
+            RenderTarget::DISPLAY_FORMAT,
+        );
+
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: DisplayParams::BUFFER_SIZE as u64,
+            mapped_at_creation: false,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            params,
+            bloom: Bloom::new(device, render_target.size),
+        }
+    }
+
+    /// Record drawing the chosen AOV into the display texture of `render_target`.
+    ///
+    /// `source` is the beauty render to show,
+    /// which may have been denoised.
+    /// Bloom is only added to the displayed beauty,
+    /// leaving `source` untouched for export.
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        render_target: &RenderTarget,
+        settings: &DisplaySettings,
+    ) {
+        puffin::profile_function!();
+
+        let bloom = settings.bloom.enabled && settings.view == Aov::Beauty;
+        if bloom {
+            self.bloom.encode(
+                device,
+                queue,
+                encoder,
+                source,
+                &settings.bloom,
+                settings.exposure,
+            );
+        }
+
+        let params = DisplayParams {
+            view: settings.view,
+            width: render_target.size.0,
+            depth_range: settings.depth_range,
+            exposure: settings.exposure,
+            bloom_intensity: if bloom { settings.bloom.intensity } else { 0. },
+        };
+        queue.write_buffer(&self.params, 0, params.as_bytes().as_slice());
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: render_target.gbuffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: render_target.aovs.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&render_target.stats_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(self.bloom.output()),
+                },
+            ],
+        });
+
+        draw_fullscreen(
+            encoder,
+            &self.pipeline,
+            &bind_group,
+            &render_target.display_view,
+        );
+    }
+}
+
+/// Read back the displayed image, as shown in the UI.
+///
+/// # Errors
+///
+/// If the GPU memory couldn't be read.
+pub fn read_display(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    render_target: &RenderTarget,
+) -> Result<image::RgbaImage> {
+    puffin::profile_function!();
+
+    let (width, height) = render_target.size;
+    let data = read_texture(
+        device,
+        queue,
+        &render_target.display_texture,
+        render_target.size,
+        4,
+    )?;
+
+    image::RgbaImage::from_raw(width, height, data).context("Display texture is the wrong size")
+}
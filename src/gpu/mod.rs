@@ -1,8 +1,22 @@
 mod connection;
-pub use connection::Connection;
-mod random_texture;
-pub use random_texture::*;
+pub use connection::{AdaptiveSettings, Connection};
 mod shaders;
 pub use shaders::*;
 mod render_target;
 pub use render_target::*;
+mod denoiser;
+mod fullscreen;
+pub use denoiser::*;
+mod bloom;
+pub use bloom::*;
+mod display;
+pub use display::*;
+mod tracer;
+pub use tracer::*;
+mod timer;
+pub use timer::*;
+mod counter;
+pub use counter::*;
+mod aov;
+pub use aov::*;
+mod readback;
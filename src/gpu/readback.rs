@@ -0,0 +1,116 @@
+//! Copy data from the GPU back to the CPU.
+//!
+//! These block until the GPU is finished, so shouldn't be used every frame.
+
+use anyhow::Result;
+
+/// Map a `MAP_READ` buffer and copy out its contents.
+fn map_staging(device: &wgpu::Device, staging: &wgpu::Buffer) -> Result<Vec<u8>> {
+    let slice = staging.slice(..);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        // If the receiver is gone there's nobody to tell
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv()??;
+
+    let data = slice.get_mapped_range().to_vec();
+    staging.unmap();
+
+    Ok(data)
+}
+
+/// Read the whole of `buffer`, which needs `COPY_SRC` usage.
+///
+/// # Errors
+///
+/// If the buffer couldn't be mapped.
+pub fn read_buffer(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffer: &wgpu::Buffer,
+) -> Result<Vec<u8>> {
+    puffin::profile_function!();
+
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback"),
+        size: buffer.size(),
+        mapped_at_creation: false,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit(Some(encoder.finish()));
+
+    map_staging(device, &staging)
+}
+
+/// Read the whole of `texture`, which needs `COPY_SRC` usage.
+///
+/// The rows are tightly packed in the output.
+///
+/// # Errors
+///
+/// If the buffer couldn't be mapped.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    size: (u32, u32),
+    bytes_per_pixel: u32,
+) -> Result<Vec<u8>> {
+    puffin::profile_function!();
+
+    let row_bytes = size.0 * bytes_per_pixel;
+    // Copies need rows to be aligned
+    let padded_row_bytes =
+        row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback"),
+        size: u64::from(padded_row_bytes) * u64::from(size.1),
+        mapped_at_creation: false,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &staging,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(size.1),
+            },
+        },
+        wgpu::Extent3d {
+            width: size.0,
+            height: size.1,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let padded = map_staging(device, &staging)?;
+
+    Ok(padded
+        .chunks_exact(padded_row_bytes as usize)
+        .flat_map(|row| &row[..row_bytes as usize])
+        .copied()
+        .collect())
+}
+
+/// Interpret bytes as little endian floats.
+#[must_use]
+pub fn bytes_to_f32s(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
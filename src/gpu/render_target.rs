@@ -1,17 +1,80 @@
+/// The textures and buffers the scene is rendered into.
+///
+/// These all depend on the render size, so are recreated together on resize.
 pub struct RenderTarget {
+    /// The accumulated HDR render.
     pub render_texture: wgpu::Texture,
     pub render_view: wgpu::TextureView,
+    /// A copy of the last frame's render, which new samples are mixed with.
+    ///
+    /// Only used by the fragment tracer, the compute tracer mixes in place.
+    pub previous_texture: wgpu::Texture,
+    pub previous_view: wgpu::TextureView,
+    /// The running mean and variance of each pixel's luminance,
+    /// used for adaptive sampling.
+    pub stats_texture: wgpu::Texture,
+    pub stats_view: wgpu::TextureView,
+    /// A copy of the last frame's stats.
+    pub previous_stats_texture: wgpu::Texture,
+    pub previous_stats_view: wgpu::TextureView,
+    /// The accumulated first hit albedo, normal and depth of each pixel.
+    pub gbuffer: wgpu::Buffer,
+    /// The accumulated AOVs of each pixel, only written when enabled.
+    pub aovs: wgpu::Buffer,
+    /// The rays each pixel has traced in total.
+    pub ray_counts: wgpu::Buffer,
+    /// The final image shown in the UI.
+    pub display_texture: wgpu::Texture,
+    pub display_view: wgpu::TextureView,
+
+    /// Used by the fragment tracer.
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    /// Used by the compute tracer, which binds the render and stats as storage textures.
+    /// Only created when the compute tracer is supported.
+    pub compute_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    pub compute_bind_group: Option<wgpu::BindGroup>,
+
     pub id: Option<egui::TextureId>,
     pub size: (u32, u32),
+    /// The size the UI shows the render at,
+    /// which it is resized to unless rendering tiles.
+    pub requested_size: (u32, u32),
 }
 
 impl RenderTarget {
+    /// The format of the accumulated render.
+    pub const RENDER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+    /// The format of the image shown in the UI.
+    pub const DISPLAY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+    /// The size in bytes of one pixel in the gbuffer as represented in HLSL.
+    pub const GBUFFER_STRIDE: u64 = 32;
+    /// The size in bytes of one pixel in the AOV buffer as represented in HLSL.
+    pub const AOVS_STRIDE: u64 = 48;
+    /// The size in bytes of one pixel's ray count.
+    pub const RAY_COUNT_STRIDE: u64 = 4;
+
+    /// The largest width and height of a render target `limits` allow,
+    /// by both the largest texture and the largest per pixel buffer.
+    #[must_use]
+    pub fn max_size(limits: &wgpu::Limits) -> u32 {
+        let buffer_size =
+            u64::from(limits.max_storage_buffer_binding_size).min(limits.max_buffer_size);
+        let max_pixels = buffer_size / Self::GBUFFER_STRIDE.max(Self::AOVS_STRIDE);
+
+        // the largest square within max_pixels
+        let max_side = (max_pixels as f64).sqrt() as u32;
+        max_side.min(limits.max_texture_dimension_2d)
+    }
+
     #[must_use]
-    pub fn create_render_texture(
+    pub fn create_texture(
         device: &wgpu::Device,
         size: (u32, u32),
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
     ) -> (wgpu::Texture, wgpu::TextureView) {
-        let render_descriptor = wgpu::TextureDescriptor {
+        let descriptor = wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
                 width: size.0,
                 height: size.1,
@@ -20,30 +83,316 @@ impl RenderTarget {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            view_formats: &[wgpu::TextureFormat::Rgba8UnormSrgb],
-            usage: wgpu::TextureUsages::COPY_SRC
-                | wgpu::TextureUsages::RENDER_ATTACHMENT
-                | wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::COPY_DST,
+            format,
+            view_formats: &[format],
+            usage,
+            label: None,
+        };
+
+        let texture = device.create_texture(&descriptor);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    fn compute_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        let storage_texture = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::ReadWrite,
+                format: Self::RENDER_FORMAT,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let storage_buffer = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
+            entries: &[
+                storage_texture(0),
+                storage_buffer(1),
+                storage_buffer(2),
+                storage_texture(3),
+                storage_buffer(4),
+            ],
+        })
+    }
+
+    fn create_render_textures(
+        device: &wgpu::Device,
+        size: (u32, u32),
+        compute: bool,
+    ) -> [(wgpu::Texture, wgpu::TextureView); 5] {
+        let storage = if compute {
+            wgpu::TextureUsages::STORAGE_BINDING
+        } else {
+            wgpu::TextureUsages::empty()
         };
+        let [render, stats] = [0, 1].map(|_| {
+            Self::create_texture(
+                device,
+                size,
+                Self::RENDER_FORMAT,
+                wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | storage,
+            )
+        });
+        let [previous, previous_stats] = [0, 1].map(|_| {
+            Self::create_texture(
+                device,
+                size,
+                Self::RENDER_FORMAT,
+                wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            )
+        });
+        let display = Self::create_texture(
+            device,
+            size,
+            Self::DISPLAY_FORMAT,
+            wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+
+        [render, previous, stats, previous_stats, display]
+    }
+
+    fn create_pixel_buffers(device: &wgpu::Device, size: (u32, u32)) -> [wgpu::Buffer; 3] {
+        let pixels = u64::from(size.0) * u64::from(size.1);
 
-        let render_texture = device.create_texture(&render_descriptor);
-        let render_view = render_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        [
+            ("gbuffer", Self::GBUFFER_STRIDE),
+            ("aovs", Self::AOVS_STRIDE),
+            ("ray counts", Self::RAY_COUNT_STRIDE),
+        ]
+        .map(|(label, stride)| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: pixels * stride,
+                mapped_at_creation: false,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            })
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        previous_view: &wgpu::TextureView,
+        gbuffer: &wgpu::Buffer,
+        aovs: &wgpu::Buffer,
+        previous_stats_view: &wgpu::TextureView,
+        ray_counts: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(previous_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: gbuffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: aovs.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(previous_stats_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: ray_counts.as_entire_binding(),
+                },
+            ],
+        })
+    }
 
-        (render_texture, render_view)
+    fn create_compute_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        render_view: &wgpu::TextureView,
+        gbuffer: &wgpu::Buffer,
+        aovs: &wgpu::Buffer,
+        stats_view: &wgpu::TextureView,
+        ray_counts: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(render_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: gbuffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: aovs.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(stats_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: ray_counts.as_entire_binding(),
+                },
+            ],
+        })
     }
 
+    /// Create the render target,
+    /// with the bind group for the compute tracer if `compute` is true.
     #[must_use]
-    pub fn new(device: &wgpu::Device, initial_size: (u32, u32)) -> Self {
-        let (render_texture, render_view) = Self::create_render_texture(device, initial_size);
+    pub fn new(device: &wgpu::Device, initial_size: (u32, u32), compute: bool) -> Self {
+        let bind_group_layout = Self::bind_group_layout(device);
+        let compute_bind_group_layout = compute.then(|| Self::compute_bind_group_layout(device));
+
+        let [(render_texture, render_view), (previous_texture, previous_view), (stats_texture, stats_view), (previous_stats_texture, previous_stats_view), (display_texture, display_view)] =
+            Self::create_render_textures(device, initial_size, compute);
+        let [gbuffer, aovs, ray_counts] = Self::create_pixel_buffers(device, initial_size);
+
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &previous_view,
+            &gbuffer,
+            &aovs,
+            &previous_stats_view,
+            &ray_counts,
+        );
+        let compute_bind_group = compute_bind_group_layout.as_ref().map(|layout| {
+            Self::create_compute_bind_group(
+                device,
+                layout,
+                &render_view,
+                &gbuffer,
+                &aovs,
+                &stats_view,
+                &ray_counts,
+            )
+        });
 
         Self {
-            id: None,
-            size: initial_size,
             render_texture,
             render_view,
+            previous_texture,
+            previous_view,
+            stats_texture,
+            stats_view,
+            previous_stats_texture,
+            previous_stats_view,
+            gbuffer,
+            aovs,
+            ray_counts,
+            display_texture,
+            display_view,
+
+            bind_group_layout,
+            bind_group,
+            compute_bind_group_layout,
+            compute_bind_group,
+
+            id: None,
+            size: initial_size,
+            requested_size: initial_size,
+        }
+    }
+
+    /// Copy this frame's render and stats to be mixed with the next frame.
+    pub fn copy_to_previous(&self, encoder: &mut wgpu::CommandEncoder) {
+        for (source, destination) in [
+            (&self.render_texture, &self.previous_texture),
+            (&self.stats_texture, &self.previous_stats_texture),
+        ] {
+            encoder.copy_texture_to_texture(
+                source.as_image_copy(),
+                destination.as_image_copy(),
+                wgpu::Extent3d {
+                    width: self.size.0,
+                    height: self.size.1,
+                    depth_or_array_layers: 1,
+                },
+            );
         }
     }
 
@@ -54,7 +403,7 @@ impl RenderTarget {
             Some(id) => {
                 egui_renderer.update_egui_texture_from_wgpu_texture(
                     device,
-                    &self.render_view,
+                    &self.display_view,
                     wgpu::FilterMode::Nearest,
                     id,
                 );
@@ -62,7 +411,7 @@ impl RenderTarget {
             None => {
                 self.id = Some(egui_renderer.register_native_texture(
                     device,
-                    &self.render_view,
+                    &self.display_view,
                     wgpu::FilterMode::Nearest,
                 ));
             }
@@ -71,6 +420,33 @@ impl RenderTarget {
 
     pub fn resize(&mut self, device: &wgpu::Device, size: (u32, u32)) {
         self.size = size;
-        (self.render_texture, self.render_view) = Self::create_render_texture(device, size);
+        [
+            (self.render_texture, self.render_view),
+            (self.previous_texture, self.previous_view),
+            (self.stats_texture, self.stats_view),
+            (self.previous_stats_texture, self.previous_stats_view),
+            (self.display_texture, self.display_view),
+        ] = Self::create_render_textures(device, size, self.compute_bind_group_layout.is_some());
+        [self.gbuffer, self.aovs, self.ray_counts] = Self::create_pixel_buffers(device, size);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.previous_view,
+            &self.gbuffer,
+            &self.aovs,
+            &self.previous_stats_view,
+            &self.ray_counts,
+        );
+        self.compute_bind_group = self.compute_bind_group_layout.as_ref().map(|layout| {
+            Self::create_compute_bind_group(
+                device,
+                layout,
+                &self.render_view,
+                &self.gbuffer,
+                &self.aovs,
+                &self.stats_view,
+                &self.ray_counts,
+            )
+        });
     }
 }
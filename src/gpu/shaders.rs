@@ -22,6 +22,9 @@ pub fn vert_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
 
 /// Load the fragment shader.
 #[must_use]
+// The SPIR-V is a constant promoted to a static,
+// it isn't copied onto the stack.
+#[allow(clippy::large_stack_arrays)]
 pub fn frag_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
     let spirv = include_spirv!(
         "src/shaders/frag.hlsl",
@@ -38,3 +41,120 @@ pub fn frag_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
         source: wgpu::ShaderSource::SpirV(Cow::Borrowed(spirv)),
     })
 }
+
+/// Load the compute shader which traces the scene in place.
+#[must_use]
+// The SPIR-V is a constant promoted to a static,
+// it isn't copied onto the stack.
+#[allow(clippy::large_stack_arrays)]
+pub fn compute_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
+    let spirv = include_spirv!(
+        "src/shaders/compute.hlsl",
+        comp,
+        hlsl,
+        // This issue is only caused when debug is on
+        // https://github.com/gfx-rs/wgpu/issues/4532
+        no_debug,
+        entry = "cs_main"
+    );
+
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(Cow::Borrowed(spirv)),
+    })
+}
+
+/// Load the vertex shader used by the post processing passes.
+#[must_use]
+pub fn fullscreen_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
+    let spirv = include_spirv!(
+        "src/shaders/fullscreen.hlsl",
+        vert,
+        hlsl,
+        // This issue is only caused when debug is on
+        // https://github.com/gfx-rs/wgpu/issues/4532
+        no_debug,
+        entry = "vs_main"
+    );
+
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(Cow::Borrowed(spirv)),
+    })
+}
+
+/// Load the a-trous denoiser fragment shader.
+#[must_use]
+pub fn atrous_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
+    let spirv = include_spirv!(
+        "src/shaders/atrous.hlsl",
+        frag,
+        hlsl,
+        // This issue is only caused when debug is on
+        // https://github.com/gfx-rs/wgpu/issues/4532
+        no_debug,
+        entry = "fs_main"
+    );
+
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(Cow::Borrowed(spirv)),
+    })
+}
+
+/// Load the fragment shader which writes the image shown in the UI.
+#[must_use]
+pub fn display_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
+    let spirv = include_spirv!(
+        "src/shaders/display.hlsl",
+        frag,
+        hlsl,
+        // This issue is only caused when debug is on
+        // https://github.com/gfx-rs/wgpu/issues/4532
+        no_debug,
+        entry = "fs_main"
+    );
+
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(Cow::Borrowed(spirv)),
+    })
+}
+
+/// Load the fragment shader which blurs the bright parts of the render.
+#[must_use]
+pub fn bloom_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
+    let spirv = include_spirv!(
+        "src/shaders/bloom.hlsl",
+        frag,
+        hlsl,
+        // This issue is only caused when debug is on
+        // https://github.com/gfx-rs/wgpu/issues/4532
+        no_debug,
+        entry = "fs_main"
+    );
+
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(Cow::Borrowed(spirv)),
+    })
+}
+
+/// Load the compute shader which sums the samples and rays of each row of the render.
+#[must_use]
+pub fn count_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
+    let spirv = include_spirv!(
+        "src/shaders/count.hlsl",
+        comp,
+        hlsl,
+        // This issue is only caused when debug is on
+        // https://github.com/gfx-rs/wgpu/issues/4532
+        no_debug,
+        entry = "cs_main"
+    );
+
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(Cow::Borrowed(spirv)),
+    })
+}
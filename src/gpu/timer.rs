@@ -0,0 +1,178 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Where in the frame a timestamp is written.
+#[derive(Clone, Copy)]
+pub enum Timestamp {
+    TraceStart,
+    TraceEnd,
+    DisplayStart,
+    DisplayEnd,
+}
+
+impl Timestamp {
+    const COUNT: u32 = 4;
+}
+
+/// How long the GPU spent on the passes of a frame.
+#[derive(Clone, Copy)]
+pub struct GpuTimings {
+    /// None if nothing was traced that frame.
+    pub trace: Option<Duration>,
+    /// Includes the bloom.
+    pub display: Duration,
+}
+
+/// Where the timer is in reading back a frame's timestamps.
+#[derive(Clone, Copy)]
+enum ReadState {
+    Idle,
+    /// The copy to the readback buffer is recorded but not yet submitted.
+    Resolved {
+        traced: bool,
+    },
+    /// Waiting for the readback buffer to be mapped.
+    Mapping {
+        traced: bool,
+    },
+}
+
+/// Times the trace and display passes with timestamp queries.
+///
+/// The timestamps are read back without blocking,
+/// so the timings arrive a few frames late and some frames aren't timed.
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per tick.
+    period: f32,
+    state: ReadState,
+    /// Set once mapping the readback buffer finishes, to whether it succeeded.
+    mapped: Arc<Mutex<Option<bool>>>,
+}
+
+impl GpuTimer {
+    /// The features needed to write timestamps between passes.
+    pub const FEATURES: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY;
+    const BUFFER_SIZE: u64 = Timestamp::COUNT as u64 * std::mem::size_of::<u64>() as u64;
+
+    /// Create the timer, or None if `device` doesn't have [`GpuTimer::FEATURES`].
+    #[must_use]
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(Self::FEATURES) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: None,
+            ty: wgpu::QueryType::Timestamp,
+            count: Timestamp::COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: Self::BUFFER_SIZE,
+            mapped_at_creation: false,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("timestamp readback"),
+            size: Self::BUFFER_SIZE,
+            mapped_at_creation: false,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            state: ReadState::Idle,
+            mapped: Arc::default(),
+        })
+    }
+
+    /// Record writing a timestamp.
+    pub fn write(&self, encoder: &mut wgpu::CommandEncoder, timestamp: Timestamp) {
+        encoder.write_timestamp(&self.query_set, timestamp as u32);
+    }
+
+    /// Record copying this frame's timestamps to be read back,
+    /// unless the last copy is still being read.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder, traced: bool) {
+        if !matches!(self.state, ReadState::Idle) {
+            return;
+        }
+
+        encoder.resolve_query_set(
+            &self.query_set,
+            0..Timestamp::COUNT,
+            &self.resolve_buffer,
+            0,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            Self::BUFFER_SIZE,
+        );
+        self.state = ReadState::Resolved { traced };
+    }
+
+    /// Start reading back the timestamps, once the encoder resolving them has been submitted.
+    pub fn map(&mut self) {
+        let ReadState::Resolved { traced } = self.state else {
+            return;
+        };
+
+        let mapped = self.mapped.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if let Ok(mut mapped) = mapped.lock() {
+                    *mapped = Some(result.is_ok());
+                }
+            });
+        self.state = ReadState::Mapping { traced };
+    }
+
+    /// The timings of the last frame read back, if they've arrived since last called.
+    pub fn read(&mut self, device: &wgpu::Device) -> Option<GpuTimings> {
+        let ReadState::Mapping { traced } = self.state else {
+            return None;
+        };
+
+        device.poll(wgpu::Maintain::Poll);
+        let mapped = self.mapped.lock().ok()?.take();
+        match mapped {
+            Some(true) => {}
+            Some(false) => {
+                // try again with the next frame
+                self.state = ReadState::Idle;
+                return None;
+            }
+            None => return None,
+        }
+
+        let ticks = self
+            .readback_buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks_exact(std::mem::size_of::<u64>())
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+            .collect::<Vec<_>>();
+        self.readback_buffer.unmap();
+        self.state = ReadState::Idle;
+
+        let duration = |start: Timestamp, end: Timestamp| {
+            let ticks = ticks[end as usize].saturating_sub(ticks[start as usize]);
+            Duration::from_nanos((ticks as f64 * f64::from(self.period)) as u64)
+        };
+
+        Some(GpuTimings {
+            trace: traced.then(|| duration(Timestamp::TraceStart, Timestamp::TraceEnd)),
+            display: duration(Timestamp::DisplayStart, Timestamp::DisplayEnd),
+        })
+    }
+}
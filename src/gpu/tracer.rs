@@ -0,0 +1,195 @@
+use super::{Connection, RenderTarget};
+
+/// The size of the compute tracer's workgroups, as in `compute.hlsl`.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Traces the scene into the render target.
+pub enum Tracer {
+    /// A compute shader which reads and writes the render in place,
+    /// used when the adapter can read and write `RenderTarget::RENDER_FORMAT` storage textures.
+    Compute(wgpu::ComputePipeline),
+    /// A fragment shader drawn over a quad,
+    /// which reads a copy of the last frame's render.
+    Fragment(wgpu::RenderPipeline),
+}
+
+impl Tracer {
+    /// Whether the adapter supports the compute tracer.
+    ///
+    /// The device must be created with
+    /// `wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES` to use it.
+    #[must_use]
+    pub fn supports_compute(adapter: &wgpu::Adapter) -> bool {
+        adapter
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+            && adapter
+                .get_texture_format_features(RenderTarget::RENDER_FORMAT)
+                .flags
+                .contains(wgpu::TextureFormatFeatureFlags::STORAGE_READ_WRITE)
+    }
+
+    /// Create the compute tracer if the render target was created for it,
+    /// otherwise the fragment tracer.
+    #[must_use]
+    pub fn new(
+        device: &wgpu::Device,
+        connection: &Connection,
+        render_target: &RenderTarget,
+    ) -> Self {
+        render_target
+            .compute_bind_group_layout
+            .as_ref()
+            .map_or_else(
+                || {
+                    Self::Fragment(Self::fragment_pipeline(
+                        device,
+                        &connection.bind_group_layout,
+                        &render_target.bind_group_layout,
+                    ))
+                },
+                |compute_layout| {
+                    Self::Compute(Self::compute_pipeline(
+                        device,
+                        &connection.bind_group_layout,
+                        compute_layout,
+                    ))
+                },
+            )
+    }
+
+    fn compute_pipeline(
+        device: &wgpu::Device,
+        connection_layout: &wgpu::BindGroupLayout,
+        render_target_layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::ComputePipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[connection_layout, render_target_layout],
+            ..Default::default()
+        });
+
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            module: &super::compute_shader(device),
+            entry_point: "cs_main",
+        })
+    }
+
+    fn fragment_pipeline(
+        device: &wgpu::Device,
+        connection_layout: &wgpu::BindGroupLayout,
+        render_target_layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[connection_layout, render_target_layout],
+            ..Default::default()
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &super::vert_shader(device),
+                entry_point: "vs_main",
+                buffers: &[Connection::vertex_buffer_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &super::frag_shader(device),
+                entry_point: "fs_main",
+                // the render and the pixel stats
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: RenderTarget::RENDER_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: RenderTarget::RENDER_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    /// The name printed at startup.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Compute(_) => "Compute",
+            Self::Fragment(_) => "Fragment",
+        }
+    }
+
+    /// Trace a frame of samples into the render target.
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        connection: &Connection,
+        render_target: &RenderTarget,
+    ) {
+        puffin::profile_function!();
+
+        match self {
+            Self::Compute(pipeline) => {
+                let Some(bind_group) = &render_target.compute_bind_group else {
+                    return;
+                };
+
+                let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+
+                cpass.set_pipeline(pipeline);
+                cpass.set_bind_group(0, &connection.bind_group, &[]);
+                cpass.set_bind_group(1, bind_group, &[]);
+
+                cpass.dispatch_workgroups(
+                    render_target.size.0.div_ceil(WORKGROUP_SIZE),
+                    render_target.size.1.div_ceil(WORKGROUP_SIZE),
+                    1,
+                );
+            }
+            Self::Fragment(pipeline) => {
+                {
+                    let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        color_attachments: &[
+                            Some(wgpu::RenderPassColorAttachment {
+                                view: &render_target.render_view,
+                                resolve_target: None,
+                                ops: wgpu::Operations::default(),
+                            }),
+                            Some(wgpu::RenderPassColorAttachment {
+                                view: &render_target.stats_view,
+                                resolve_target: None,
+                                ops: wgpu::Operations::default(),
+                            }),
+                        ],
+                        ..Default::default()
+                    });
+
+                    rpass.set_pipeline(pipeline);
+                    rpass.set_bind_group(0, &connection.bind_group, &[]);
+                    rpass.set_bind_group(1, &render_target.bind_group, &[]);
+
+                    rpass.set_vertex_buffer(0, connection.vertex_buffer.slice(..));
+                    rpass.set_index_buffer(
+                        connection.index_buffer.slice(..),
+                        wgpu::IndexFormat::Uint16,
+                    );
+
+                    rpass.draw_indexed(0..(Connection::INDICES_NUM as u32), 0, 0..1);
+                }
+
+                render_target.copy_to_previous(encoder);
+            }
+        }
+    }
+}
@@ -29,11 +29,20 @@ use app::App;
 pub use ui::Ui;
 
 mod app;
+mod args;
+mod bindings;
 mod bytes;
+mod controls;
+mod convergence;
 mod gpu;
 mod movement;
 mod panels;
+mod progress;
 mod ray_tracer;
+mod sequence;
+mod settings;
+mod statistics;
+mod tiled;
 mod time;
 
 use anyhow::Result;
@@ -46,5 +55,5 @@ pub fn main() -> Result<()> {
         std::env::set_var("RUST_BACKTRACE", "1");
     }
 
-    App::run()
+    App::run(&args::Args::parse()?)
 }
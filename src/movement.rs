@@ -1,57 +1,129 @@
-use egui::{InputState, Key};
+use egui::InputState;
 
-use crate::ray_tracer::Camera;
+use crate::bindings::Action;
+use crate::controls::Controls;
+use crate::ray_tracer::{Camera, Vec3};
+
+/// How the camera is moved around the scene.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CameraMode {
+    /// Move with WASDQE and look around with the arrow keys, or whatever they're rebound to.
+    Fly,
+    /// Drag to turn around the pivot, middle drag to pan and scroll to move closer.
+    Orbit {
+        /// The point the camera turns around.
+        pivot: Vec3,
+    },
+}
+
+impl CameraMode {
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Fly => "Fly",
+            Self::Orbit { .. } => "Orbit",
+        }
+    }
+}
+
+/// Look around by the mouse moving `delta` pixels.
+pub fn mouse_look(camera: &mut Camera, controls: &Controls, delta: egui::Vec2) {
+    let pitch = if controls.invert_y { -delta.y } else { delta.y };
+
+    camera.turn(delta.x * controls.mouse_sensitivity);
+    camera.pitch(pitch * controls.mouse_sensitivity);
+}
+
+/// Turn the camera around `pivot` by `yaw` and `pitch` radians,
+/// keeping the pivot in the same place on screen.
+pub fn orbit(camera: &mut Camera, pivot: Vec3, yaw: f32, pitch: f32) {
+    let old_orientation = camera.orientation;
+    camera.turn(yaw);
+    camera.pitch(pitch);
+
+    let rotation = camera.orientation * old_orientation.inverse();
+    camera.position = pivot + rotation * (camera.position - pivot);
+}
+
+/// Move the camera and pivot across the screen by `x` and `y` pixels,
+/// further when the pivot is further away.
+pub fn pan(camera: &mut Camera, pivot: &mut Vec3, x: f32, y: f32) {
+    let (_, right, up) = camera.get_vectors_fru();
+    let scale = (camera.position - *pivot).magnitude() * 0.002;
+
+    let offset = (up * y - right * x) * scale;
+    camera.position += offset;
+    *pivot += offset;
+}
+
+/// Move the camera towards the pivot by `amount` scrolled pixels, never reaching it.
+pub fn dolly(camera: &mut Camera, pivot: Vec3, amount: f32) {
+    let offset = camera.position - pivot;
+    let Some(direction) = offset.try_normalize(f32::EPSILON) else {
+        return;
+    };
+    let distance = (offset.magnitude() * (-amount * 0.002).exp()).max(0.01);
+
+    camera.position = pivot + direction * distance;
+}
 
 /// Update the camera position and rotation based on egui input.
 pub fn move_and_rotate(
     input: &InputState,
     camera: &mut Camera,
-    look_speed: f32,
-    move_speed: f32,
-    shift_mod: f32,
-    ctrl_mod: f32,
+    controls: &Controls,
+    delta_time: f32,
 ) {
-    if input.key_down(Key::ArrowRight) {
-        camera.rotation.y += look_speed;
+    let bindings = &controls.bindings;
+    let look_speed = controls.look_speed * delta_time;
+
+    if bindings.is_down(input, Action::LookRight) {
+        camera.turn(look_speed);
     }
-    if input.key_down(Key::ArrowLeft) {
-        camera.rotation.y -= look_speed;
+    if bindings.is_down(input, Action::LookLeft) {
+        camera.turn(-look_speed);
     }
-    if input.key_down(Key::ArrowUp) {
-        camera.rotation.x -= look_speed;
+    if bindings.is_down(input, Action::LookUp) {
+        camera.pitch(-look_speed);
     }
-    if input.key_down(Key::ArrowDown) {
-        camera.rotation.x += look_speed;
+    if bindings.is_down(input, Action::LookDown) {
+        camera.pitch(look_speed);
+    }
+    if bindings.is_down(input, Action::RollLeft) {
+        camera.roll(-look_speed);
+    }
+    if bindings.is_down(input, Action::RollRight) {
+        camera.roll(look_speed);
     }
 
-    camera.clamp_rotation();
-
-    let move_speed = if input.modifiers.shift {
-        move_speed * shift_mod
-    } else if input.modifiers.ctrl {
-        move_speed * ctrl_mod
+    let move_speed = controls.move_speed * delta_time;
+    let move_speed = if bindings.is_down(input, Action::SpeedBoost) {
+        move_speed * controls.fast_multiplier
+    } else if bindings.is_down(input, Action::SlowDown) {
+        move_speed * controls.slow_multiplier
     } else {
         move_speed
     };
 
     let (forward, right, up) = camera.get_vectors_fru();
 
-    if input.key_down(Key::W) {
+    if bindings.is_down(input, Action::MoveForward) {
         camera.position -= forward * move_speed;
     }
-    if input.key_down(Key::S) {
+    if bindings.is_down(input, Action::MoveBackward) {
         camera.position += forward * move_speed;
     }
-    if input.key_down(Key::D) {
+    if bindings.is_down(input, Action::MoveRight) {
         camera.position += right * move_speed;
     }
-    if input.key_down(Key::A) {
+    if bindings.is_down(input, Action::MoveLeft) {
         camera.position -= right * move_speed;
     }
-    if input.key_down(Key::E) {
+    if bindings.is_down(input, Action::MoveUp) {
         camera.position += up * move_speed;
     }
-    if input.key_down(Key::Q) {
+    if bindings.is_down(input, Action::MoveDown) {
         camera.position -= up * move_speed;
     }
 }
@@ -1,6 +1,19 @@
+use std::iter;
 use std::ops::{Add, Div};
+use std::path::Path;
 
-use crate::ray_tracer::{Geometry, Object, Scene, Vec3};
+use crate::bindings::{Action, Binding, InputMap};
+use crate::controls::Controls;
+use crate::gpu::{Aov, Denoiser};
+use crate::movement::CameraMode;
+use crate::progress::Progress;
+use crate::ray_tracer::{
+    Animation, Bookmark, Camera, Geometry, Interpolation, Object, PhysicalCamera, Physics,
+    Projection, Property, Sampler, Scene, ScenePreset, Stereo, StereoLayout, Target, Vec3, Views,
+};
+use crate::sequence::FrameFormat;
+use crate::settings::{Request, Settings};
+use crate::statistics::Statistics;
 use puffin::GlobalFrameView;
 
 fn vec3_widget(ui: &mut egui::Ui, vec3: &mut Vec3) {
@@ -40,10 +53,223 @@ fn data_row(
     });
 }
 
+/// The widgets specific to each type of geometry.
+fn geometry_widget(ui: &mut egui::Ui, geometry: &mut Geometry) {
+    match geometry {
+        Geometry::Sphere { center: _, radius } => {
+            data_row(ui, "radius", |ui| {
+                ui.add(egui::DragValue::new(radius).fixed_decimals(1).speed(0.1));
+            });
+        }
+        Geometry::Plane {
+            center: _,
+            normal,
+            size,
+        } => {
+            data_row(ui, "normal", |ui| {
+                vec3_widget(ui, normal);
+
+                *normal = normal.normalize();
+            });
+
+            data_row(ui, "size", |ui| {
+                ui.add(egui::DragValue::new(size).fixed_decimals(1).speed(0.1));
+            });
+        }
+        Geometry::Quad { center: _, u, v } => {
+            data_row(ui, "u", |ui| {
+                vec3_widget(ui, u);
+            });
+            data_row(ui, "v", |ui| {
+                vec3_widget(ui, v);
+            });
+        }
+    }
+}
+
+/// The saved camera views.
+fn views_widget(ui: &mut egui::Ui, scene: &mut Scene, views: &mut Views) {
+    ui.heading("Views");
+
+    ui.horizontal(|ui| {
+        if ui.button("➕ save current view").clicked() {
+            scene.bookmarks.push(Bookmark {
+                name: format!("View {}", scene.bookmarks.len() + 1),
+                camera: scene.camera.clone(),
+            });
+        }
+        ui.checkbox(&mut views.smooth, "smooth");
+        ui.add_enabled(
+            views.smooth,
+            egui::DragValue::new(&mut views.duration)
+                .clamp_range::<f32>(0.1..=10.)
+                .speed(0.1)
+                .suffix(" s"),
+        );
+    });
+
+    let mut go_to = None;
+    let mut removed = None;
+
+    for (index, bookmark) in scene.bookmarks.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let mut button = ui.button("Go to");
+            if index < 9 {
+                button = button.on_hover_text(format!("Or press {}", index + 1));
+            }
+            if button.clicked() {
+                go_to = Some(index);
+            }
+
+            ui.text_edit_singleline(&mut bookmark.name);
+            if ui.button("❌").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+
+    if let Some(index) = go_to {
+        views.go_to(scene, index);
+    }
+    if let Some(index) = removed {
+        scene.bookmarks.remove(index);
+    }
+}
+
+/// The camera controls, returning whether any changed.
+pub fn controls_widget(ui: &mut egui::Ui, controls: &mut Controls) -> bool {
+    let mut changed = false;
+
+    data_row(ui, "Move speed", |ui| {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut controls.move_speed)
+                    .clamp_range(Controls::MOVE_SPEED_RANGE)
+                    .speed(0.1),
+            )
+            .changed();
+    });
+    data_row(ui, "Shift multiplier", |ui| {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut controls.fast_multiplier)
+                    .clamp_range::<f32>(1.0..=100.)
+                    .speed(0.1),
+            )
+            .changed();
+    });
+    data_row(ui, "Ctrl multiplier", |ui| {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut controls.slow_multiplier)
+                    .clamp_range::<f32>(0.01..=1.)
+                    .speed(0.01),
+            )
+            .changed();
+    });
+    data_row(ui, "Arrow key look speed", |ui| {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut controls.look_speed)
+                    .clamp_range::<f32>(0.1..=10.)
+                    .speed(0.05),
+            )
+            .changed();
+    });
+    data_row(ui, "Mouse sensitivity", |ui| {
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut controls.mouse_sensitivity)
+                    .clamp_range::<f32>(0.0001..=0.05)
+                    .speed(0.0001)
+                    .max_decimals(4),
+            )
+            .changed();
+    });
+    data_row(ui, "Invert mouse Y", |ui| {
+        changed |= ui.checkbox(&mut controls.invert_y, "").changed();
+    });
+
+    if ui.button("Reset to defaults").clicked() {
+        let bindings = controls.bindings.clone();
+        *controls = Controls {
+            bindings,
+            ..Controls::default()
+        };
+        changed = true;
+    }
+
+    changed
+}
+
+/// The key bound to each action, clicked to rebind,
+/// returning whether any were rebound.
+pub fn bindings_widget(
+    ui: &mut egui::Ui,
+    bindings: &mut InputMap,
+    rebinding: &mut Option<Action>,
+) -> bool {
+    let mut changed = false;
+
+    if let Some(action) = *rebinding {
+        let pressed = ui.input(Binding::pressed);
+        if pressed == Some(Binding::Key(egui::Key::Escape)) {
+            *rebinding = None;
+        } else if let Some(binding) = pressed {
+            bindings.bind(action, binding);
+            *rebinding = None;
+            changed = true;
+        }
+    }
+
+    for action in Action::ALL {
+        let conflicts = bindings
+            .conflicts(action)
+            .map(Action::name)
+            .collect::<Vec<_>>();
+
+        data_row(ui, action.name(), |ui| {
+            let text = if *rebinding == Some(action) {
+                "press a key...".into()
+            } else if conflicts.is_empty() {
+                egui::RichText::new(bindings.binding(action).name())
+            } else {
+                egui::RichText::new(format!("⚠ {}", bindings.binding(action).name()))
+                    .color(ui.visuals().warn_fg_color)
+            };
+
+            let mut button = ui.button(text);
+            if !conflicts.is_empty() {
+                button = button.on_hover_text(format!("Also bound to {}", conflicts.join(", ")));
+            }
+            if button.clicked() {
+                *rebinding = Some(action);
+            }
+        });
+    }
+
+    if ui.button("Reset to defaults").clicked() {
+        *bindings = InputMap::default();
+        *rebinding = None;
+        changed = true;
+    }
+
+    changed
+}
+
 /// The objects panel.
-pub fn object_panel(ui: &mut egui::Ui, scene: &mut Scene) {
+pub fn object_panel(
+    ui: &mut egui::Ui,
+    scene: &mut Scene,
+    views: &mut Views,
+    camera_mode: &mut CameraMode,
+) {
     puffin::profile_function!();
 
+    views_widget(ui, scene, views);
+
+    ui.separator();
+
     ui.horizontal(|ui| {
         if ui.add(egui::Button::new("➕ sphere")).clicked() {
             scene.objects.push(Object::default_sphere());
@@ -51,14 +277,13 @@ pub fn object_panel(ui: &mut egui::Ui, scene: &mut Scene) {
         if ui.add(egui::Button::new("➕ plane")).clicked() {
             scene.objects.push(Object::default_plane());
         }
+        if ui.add(egui::Button::new("➕ quad")).clicked() {
+            scene.objects.push(Object::default_quad());
+        }
     });
 
     ui.separator();
 
-    ui.checkbox(&mut scene.do_objects_spin, "spin");
-
-    ui.separator();
-
     egui::ScrollArea::vertical()
         .id_source("Objects")
         .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible)
@@ -73,6 +298,15 @@ pub fn object_panel(ui: &mut egui::Ui, scene: &mut Scene) {
                     .default_open(index == 0)
                     .show(ui, |ui| {
                         data_row(ui, name, |ui| {
+                            if ui
+                                .add(egui::Button::new("🎯"))
+                                .on_hover_text("Orbit around this object")
+                                .clicked()
+                            {
+                                *camera_mode = CameraMode::Orbit {
+                                    pivot: *scene.objects[index].geometry.position(),
+                                };
+                            }
                             if ui.add(egui::Button::new("❌")).clicked() {
                                 scene.objects.remove(index);
                                 has_removed_object = true;
@@ -89,29 +323,20 @@ pub fn object_panel(ui: &mut egui::Ui, scene: &mut Scene) {
                             vec3_widget(ui, object.geometry.position_as_mut());
                         });
 
-                        match &mut object.geometry {
-                            Geometry::Sphere { center: _, radius } => {
-                                data_row(ui, "radius", |ui| {
-                                    ui.add(
-                                        egui::DragValue::new(radius).fixed_decimals(1).speed(0.1),
-                                    );
-                                });
-                            }
-                            Geometry::Plane {
-                                center: _,
-                                normal,
-                                size,
-                            } => {
-                                data_row(ui, "normal", |ui| {
-                                    vec3_widget(ui, normal);
-
-                                    *normal = normal.normalize();
-                                });
-
-                                data_row(ui, "size", |ui| {
-                                    ui.add(egui::DragValue::new(size).fixed_decimals(1).speed(0.1));
-                                });
-                            }
+                        geometry_widget(ui, &mut object.geometry);
+
+                        if let Geometry::Sphere { .. } = object.geometry {
+                            data_row(ui, "mass", |ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut object.body.mass)
+                                        .clamp_range::<f32>(0.001..=10_000.)
+                                        .speed(0.1)
+                                        .suffix(" kg"),
+                                );
+                            });
+                            data_row(ui, "static", |ui| {
+                                ui.checkbox(&mut object.body.is_static, "");
+                            });
                         }
 
                         data_row(ui, "colour", |ui| {
@@ -150,6 +375,180 @@ pub fn object_panel(ui: &mut egui::Ui, scene: &mut Scene) {
         });
 }
 
+/// The keyframe added by the timeline panel's key button.
+pub struct KeyEditor {
+    target: Target,
+    property: Property,
+    interpolation: Interpolation,
+}
+
+impl Default for KeyEditor {
+    fn default() -> Self {
+        Self {
+            target: Target::Camera,
+            property: Property::Position,
+            interpolation: Interpolation::Linear,
+        }
+    }
+}
+
+fn target_name(scene: &Scene, target: Target) -> String {
+    match target {
+        Target::Camera => "Camera".to_string(),
+        Target::Object(id) => scene
+            .objects
+            .iter()
+            .find(|object| object.id == id)
+            .map_or_else(
+                || "Missing object".to_string(),
+                |object| object.name.clone(),
+            ),
+        Target::Light(index) => format!("Light {index}"),
+    }
+}
+
+/// The timeline, with playback controls and the keyframed tracks.
+pub fn timeline_panel(ui: &mut egui::Ui, scene: &mut Scene, key_editor: &mut KeyEditor) {
+    puffin::profile_function!();
+
+    ui.horizontal(|ui| {
+        let animation = &mut scene.animation;
+
+        if ui
+            .button(if animation.playing { "⏸" } else { "▶" })
+            .clicked()
+        {
+            if !animation.playing && animation.time >= animation.duration {
+                animation.time = 0.;
+            }
+            animation.playing = !animation.playing;
+        }
+        ui.checkbox(&mut animation.looping, "loop");
+        ui.add(
+            egui::DragValue::new(&mut animation.duration)
+                .clamp_range::<f32>(0.1..=3600.)
+                .speed(0.1)
+                .suffix(" s"),
+        )
+        .on_hover_text("The length of the timeline");
+
+        ui.spacing_mut().slider_width = (ui.available_width() - 250.).max(100.);
+        ui.add(
+            egui::Slider::new(&mut animation.time, 0.0..=animation.duration)
+                .fixed_decimals(2)
+                .suffix(" s"),
+        );
+
+        if ui
+            .button("Spin")
+            .on_hover_text("Replaces the animation with every sphere going around the origin")
+            .clicked()
+        {
+            scene.animation = Animation::spin(&scene.objects);
+        }
+        if ui.button("Clear").clicked() {
+            scene.animation.tracks.clear();
+        }
+    });
+
+    key_editor_widget(ui, scene, key_editor);
+    tracks_widget(ui, scene);
+}
+
+/// Choose a property and key its current value.
+fn key_editor_widget(ui: &mut egui::Ui, scene: &mut Scene, key_editor: &mut KeyEditor) {
+    ui.horizontal(|ui| {
+        let targets = iter::once(Target::Camera)
+            .chain(scene.objects.iter().map(|object| Target::Object(object.id)))
+            .chain((0..scene.lights.len()).map(Target::Light));
+
+        egui::ComboBox::from_id_source("key target")
+            .selected_text(target_name(scene, key_editor.target))
+            .show_ui(ui, |ui| {
+                for target in targets {
+                    ui.selectable_value(&mut key_editor.target, target, target_name(scene, target));
+                }
+            });
+
+        let properties = Property::of(key_editor.target);
+        if !properties.contains(&key_editor.property) {
+            key_editor.property = properties[0];
+        }
+        egui::ComboBox::from_id_source("key property")
+            .selected_text(key_editor.property.name())
+            .show_ui(ui, |ui| {
+                for &property in properties {
+                    ui.selectable_value(&mut key_editor.property, property, property.name());
+                }
+            });
+
+        egui::ComboBox::from_id_source("key interpolation")
+            .selected_text(key_editor.interpolation.name())
+            .show_ui(ui, |ui| {
+                for interpolation in Interpolation::ALL {
+                    ui.selectable_value(
+                        &mut key_editor.interpolation,
+                        interpolation,
+                        interpolation.name(),
+                    );
+                }
+            });
+
+        if ui
+            .button("Key")
+            .on_hover_text("Keys the current value at the current time")
+            .clicked()
+        {
+            scene.set_key(
+                key_editor.target,
+                key_editor.property,
+                key_editor.interpolation,
+            );
+        }
+    });
+}
+
+/// Each track with its keyframes, which jump to their time when clicked.
+fn tracks_widget(ui: &mut egui::Ui, scene: &mut Scene) {
+    let mut removed = None;
+    let mut jump_to = None;
+
+    egui::ScrollArea::vertical()
+        .id_source("Tracks")
+        .max_height(120.)
+        .show(ui, |ui| {
+            for (index, track) in scene.animation.tracks.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.button("❌").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.label(format!(
+                        "{} {}",
+                        target_name(scene, track.target),
+                        track.property.name()
+                    ));
+
+                    for key in &track.keys {
+                        if ui
+                            .small_button(format!("{:.2}", key.time))
+                            .on_hover_text(key.interpolation.name())
+                            .clicked()
+                        {
+                            jump_to = Some(key.time);
+                        }
+                    }
+                });
+            }
+        });
+
+    if let Some(index) = removed {
+        scene.animation.tracks.remove(index);
+    }
+    if let Some(time) = jump_to {
+        scene.animation.time = time;
+    }
+}
+
 fn average<T: Default + Add<Output = T> + Div<Output = T> + From<u32>>(
     iter: impl Iterator<Item = T>,
 ) -> T {
@@ -182,9 +581,95 @@ pub fn settings_panel(
     global_frame_view: &GlobalFrameView,
     show_profiler: &mut bool,
     scene: &mut Scene,
+    physics: &mut Physics,
+    settings: &mut Settings,
+    progress: &Progress,
 ) {
     puffin::profile_function!();
 
+    ui.horizontal(|ui| {
+        ui.menu_button("Load scene", |ui| {
+            for preset in ScenePreset::ALL {
+                if ui.button(preset.name()).clicked() {
+                    *scene = preset.scene();
+                    ui.close_menu();
+                }
+            }
+        });
+
+        if ui.button("Open").clicked() {
+            match Scene::load(Path::new(&settings.scene_path)) {
+                Ok(loaded) => *scene = loaded,
+                Err(error) => eprintln!("Opening the scene failed with error: {error:#}"),
+            }
+        }
+        if ui
+            .button("Save")
+            .on_hover_text("Saves the scene and its animation as JSON")
+            .clicked()
+        {
+            if let Err(error) = scene.save(Path::new(&settings.scene_path)) {
+                eprintln!("Saving the scene failed with error: {error:#}");
+            }
+        }
+    });
+    data_row(ui, "scene file", |ui| {
+        ui.text_edit_singleline(&mut settings.scene_path);
+    });
+
+    ui.separator();
+
+    budget_settings(ui, settings, progress);
+
+    ui.separator();
+
+    fps_settings(ui, global_frame_view, show_profiler);
+    render_statistics(ui, &progress.statistics);
+
+    ui.separator();
+
+    let had_physical_camera = scene.physical_camera.is_some();
+    scene_settings(ui, scene);
+    // back to the exposure the physical camera replaced
+    if had_physical_camera && scene.physical_camera.is_none() {
+        settings.display.exposure = 1.;
+    }
+
+    ui.separator();
+
+    physics_settings(ui, scene, physics);
+
+    ui.separator();
+
+    output_settings(ui, settings);
+
+    ui.separator();
+
+    tiled_settings(ui, settings, progress);
+
+    ui.separator();
+
+    sequence_settings(ui, settings, progress);
+
+    ui.separator();
+
+    adaptive_settings(ui, settings);
+
+    ui.separator();
+
+    convergence_plot(ui, settings, progress);
+
+    ui.separator();
+
+    denoise_settings(ui, settings);
+
+    ui.separator();
+
+    bloom_settings(ui, settings);
+}
+
+/// The frame rate, its history and the profiler toggles.
+fn fps_settings(ui: &mut egui::Ui, global_frame_view: &GlobalFrameView, show_profiler: &mut bool) {
     ui.heading("Fps");
 
     let mut profiling = puffin::are_scopes_on();
@@ -240,21 +725,803 @@ pub fn settings_panel(
         .show(ui, |ui| {
             ui.line(egui_plot::Line::new(plot_points).name("Fps history"));
         });
+}
 
-    ui.separator();
+/// How fast the render is accumulating, and how long the GPU takes on it when it can be timed.
+fn render_statistics(ui: &mut egui::Ui, statistics: &Statistics) {
+    let Some(latest) = statistics.latest() else {
+        return;
+    };
+
+    // the counts are read back from the GPU, so are missing for the first few frames
+    let counts = statistics.latest_counts();
+    let millions = |count: f64| format!("{:.2} M", count / 1e6);
+    data_row(ui, "samples per pixel", |ui| {
+        ui.label(counts.map_or_else(
+            || "-".to_string(),
+            |counts| format!("{:.1}", counts.samples_per_pixel()),
+        ))
+        .on_hover_text("The mean over every pixel, which adaptive sampling varies");
+    });
+    data_row(ui, "total samples", |ui| {
+        ui.label(counts.map_or_else(|| "-".to_string(), |counts| millions(counts.samples as f64)));
+    });
+    data_row(ui, "rays per second", |ui| {
+        ui.label(millions(latest.rays_per_second))
+            .on_hover_text("Every bounce of every path is a ray");
+    });
+
+    let now = latest.time;
+    let seconds_ago = |time: std::time::Instant| -(now - time).as_secs_f64();
+
+    let rays = statistics
+        .history()
+        .map(|frame| [seconds_ago(frame.time), frame.rays_per_second / 1e6])
+        .collect::<Vec<_>>();
 
+    egui_plot::Plot::new("Rays history")
+        .x_axis_label("Seconds")
+        .y_axis_label("Million rays per second")
+        .height(200.)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .include_y(0.)
+        .show(ui, |ui| {
+            ui.line(egui_plot::Line::new(rays).name("Rays per second"));
+        });
+
+    if !statistics.gpu_timing {
+        ui.label("GPU timing is unsupported by this adapter");
+        return;
+    }
+
+    if let Some(timings) = statistics.latest_timings() {
+        data_row(ui, "trace", |ui| {
+            ui.label(timings.trace.map_or_else(
+                || "idle".to_string(),
+                |trace| format!("{:.2} ms", trace.as_secs_f64() * 1000.),
+            ));
+        });
+        data_row(ui, "display", |ui| {
+            ui.label(format!("{:.2} ms", timings.display.as_secs_f64() * 1000.));
+        });
+    }
+
+    let timed = statistics
+        .history()
+        .filter_map(|frame| Some((seconds_ago(frame.time), frame.timings?)));
+    let (trace, display): (Vec<_>, Vec<_>) = timed
+        .map(|(x, timings)| {
+            (
+                timings.trace.map(|trace| [x, trace.as_secs_f64() * 1000.]),
+                [x, timings.display.as_secs_f64() * 1000.],
+            )
+        })
+        .unzip();
+    let trace = trace.into_iter().flatten().collect::<Vec<_>>();
+
+    egui_plot::Plot::new("GPU time history")
+        .x_axis_label("Seconds")
+        .y_axis_label("GPU milliseconds")
+        .height(200.)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .include_y(0.)
+        .legend(egui_plot::Legend::default())
+        .show(ui, |ui| {
+            ui.line(egui_plot::Line::new(trace).name("Trace"));
+            ui.line(egui_plot::Line::new(display).name("Display"));
+        });
+}
+
+/// The projection and the settings it uses.
+///
+/// The fov can't be changed while it's set by the physical camera.
+fn projection_settings(ui: &mut egui::Ui, camera: &mut Camera, has_physical_camera: bool) {
+    data_row(ui, "projection", |ui| {
+        egui::ComboBox::from_id_source("projection")
+            .selected_text(camera.projection.name())
+            .show_ui(ui, |ui| {
+                for projection in Projection::ALL {
+                    let selected = camera.projection.index() == projection.index();
+                    if ui.selectable_label(selected, projection.name()).clicked() && !selected {
+                        camera.projection = projection;
+                        // fisheyes can be wider than perspective can show
+                        camera.fov = camera.fov.min(90.);
+                    }
+                }
+            });
+    });
+
+    match &mut camera.projection {
+        Projection::Perspective => {
+            data_row(ui, "fov", |ui| {
+                ui.add_enabled(
+                    !has_physical_camera,
+                    egui::DragValue::new(&mut camera.fov).clamp_range::<f64>(1.0..=90.),
+                );
+            });
+        }
+        Projection::Orthographic { width } => {
+            data_row(ui, "width", |ui| {
+                ui.add(
+                    egui::DragValue::new(width)
+                        .clamp_range::<f32>(0.01..=1000.)
+                        .speed(0.1),
+                )
+                .on_hover_text("The width of the image in world units");
+            });
+        }
+        Projection::Equirectangular => {}
+        Projection::Fisheye => {
+            data_row(ui, "fov", |ui| {
+                ui.add(egui::DragValue::new(&mut camera.fov).clamp_range::<f64>(1.0..=360.))
+                    .on_hover_text("The angle across the width of the image");
+            });
+        }
+    }
+}
+
+/// Rendering both eyes side by side or above each other.
+fn stereo_settings(ui: &mut egui::Ui, stereo: &mut Stereo) {
+    data_row(ui, "stereo", |ui| {
+        egui::ComboBox::from_id_source("stereo")
+            .selected_text(stereo.layout.name())
+            .show_ui(ui, |ui| {
+                for layout in StereoLayout::ALL {
+                    ui.selectable_value(&mut stereo.layout, layout, layout.name());
+                }
+            })
+            .response
+            .on_hover_text("Omni-directional stereo with the equirectangular projection");
+    });
+
+    if stereo.layout != StereoLayout::Off {
+        data_row(ui, "eye separation", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut stereo.eye_separation)
+                    .clamp_range::<f32>(0.0..=10.)
+                    .speed(0.001)
+                    .max_decimals(3),
+            )
+            .on_hover_text("The distance between the eyes, 0.064 for people at 1 unit per metre");
+        });
+    }
+}
+
+/// The camera and render settings stored in the scene.
+fn scene_settings(ui: &mut egui::Ui, scene: &mut Scene) {
     data_row(ui, "position", |ui| {
         vec3_widget(ui, &mut scene.camera.position);
     });
     data_row(ui, "rotation", |ui| {
-        vec3_widget(ui, &mut scene.camera.rotation);
+        let euler = scene.camera.euler();
+        let mut edited = euler;
+        vec3_widget(ui, &mut edited);
+        if edited != euler {
+            scene.camera.set_euler(edited);
+        }
     });
 
     data_row(ui, "bounces", |ui| {
-        ui.add(egui::DragValue::new(&mut scene.reflection_limit).clamp_range::<u32>(2..=20));
+        ui.add(egui::DragValue::new(&mut scene.reflection_limit).clamp_range::<u32>(2..=100))
+            .on_hover_text("The most bounces a path can take");
+    });
+
+    data_row(ui, "russian roulette", |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut scene.russian_roulette, "");
+            ui.add_enabled(
+                scene.russian_roulette,
+                egui::DragValue::new(&mut scene.roulette_depth)
+                    .clamp_range::<u32>(1..=100)
+                    .prefix("after "),
+            )
+            .on_hover_text("Randomly end dim paths after this many bounces");
+        });
+    });
+
+    let clamp_tooltip = "Limits the brightest light a single sample can gather, \
+        which removes fireflies from small bright lights, \
+        at the cost of bias: bright highlights and caustics lose energy \
+        so the render converges to a darker image than the reference.";
+    data_row(ui, "clamp direct", |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut scene.fireflies.clamp_radiance, "")
+                .on_hover_text(clamp_tooltip);
+            ui.add_enabled(
+                scene.fireflies.clamp_radiance,
+                egui::DragValue::new(&mut scene.fireflies.clamp_direct)
+                    .clamp_range::<f32>(0.01..=1000.)
+                    .speed(0.1),
+            )
+            .on_hover_text(clamp_tooltip);
+        });
+    });
+    data_row(ui, "clamp indirect", |ui| {
+        ui.add_enabled(
+            scene.fireflies.clamp_radiance,
+            egui::DragValue::new(&mut scene.fireflies.clamp_indirect)
+                .clamp_range::<f32>(0.01..=1000.)
+                .speed(0.1),
+        )
+        .on_hover_text(clamp_tooltip);
+    });
+
+    data_row(ui, "path roughening", |ui| {
+        ui.checkbox(&mut scene.fireflies.path_roughening, "")
+            .on_hover_text(
+                "Treats surfaces hit after a rough bounce as at least as rough, \
+            which removes fireflies from sharp reflections of small lights, \
+            at the cost of bias: caustics and glossy inter-reflections are blurred.",
+            );
+    });
+
+    let has_physical_camera = scene.physical_camera.is_some();
+    projection_settings(ui, &mut scene.camera, has_physical_camera);
+    stereo_settings(ui, &mut scene.camera.stereo);
+
+    physical_camera_settings(ui, scene);
+
+    motion_blur_settings(ui, scene);
+
+    data_row(ui, "seed", |ui| {
+        ui.add(egui::DragValue::new(&mut scene.seed));
+    });
+
+    data_row(ui, "sampler", |ui| {
+        egui::ComboBox::from_id_source("sampler")
+            .selected_text(scene.sampler.name())
+            .show_ui(ui, |ui| {
+                for sampler in Sampler::ALL {
+                    ui.selectable_value(&mut scene.sampler, sampler, sampler.name());
+                }
+            });
+    });
+
+    data_row(ui, "hdri", |ui| {
+        ui.checkbox(&mut scene.use_hdri, "");
+    });
+
+    if !scene.use_hdri {
+        data_row(ui, "background", |ui| {
+            colour_widget(ui, &mut scene.background_colour);
+        });
+    }
+}
+
+fn physics_settings(ui: &mut egui::Ui, scene: &mut Scene, physics: &mut Physics) {
+    ui.heading("Physics");
+
+    ui.horizontal(|ui| {
+        if ui.button(if physics.playing { "⏸" } else { "▶" }).clicked() {
+            physics.playing = !physics.playing;
+        }
+        if ui.button("Step").clicked() {
+            physics.step_once();
+        }
+        if ui
+            .button("Reset")
+            .on_hover_text("Moves the spheres back to where they started")
+            .clicked()
+        {
+            physics.reset(scene);
+        }
+    });
+
+    let settings = &mut physics.settings;
+
+    data_row(ui, "gravity", |ui| {
+        vec3_widget(ui, &mut settings.gravity);
+    });
+    data_row(ui, "restitution", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut settings.restitution)
+                .clamp_range::<f32>(0.0..=1.)
+                .speed(0.01),
+        )
+        .on_hover_text("The fraction of speed kept after bouncing");
+    });
+    data_row(ui, "friction", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut settings.friction)
+                .clamp_range::<f32>(0.0..=1.)
+                .speed(0.01),
+        );
+    });
+    data_row(ui, "steps per second", |ui| {
+        ui.add(egui::DragValue::new(&mut settings.steps_per_second).clamp_range(10..=1000))
+            .on_hover_text("The simulation runs at this fixed rate, regardless of the frame rate");
+    });
+}
+
+/// A real camera's settings, which set the fov, depth of field, shutter and exposure.
+fn physical_camera_settings(ui: &mut egui::Ui, scene: &mut Scene) {
+    data_row(ui, "physical camera", |ui| {
+        let mut enabled = scene.physical_camera.is_some();
+        if ui
+            .checkbox(&mut enabled, "")
+            .on_hover_text("Set the fov, depth of field, shutter and exposure like a real camera")
+            .changed()
+        {
+            scene.physical_camera = enabled.then(PhysicalCamera::default);
+        }
+    });
+
+    let Some(physical) = &mut scene.physical_camera else {
+        return;
+    };
+
+    data_row(ui, "sensor width", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut physical.sensor_width)
+                .clamp_range::<f32>(1.0..=100.)
+                .speed(0.1)
+                .suffix(" mm"),
+        )
+        .on_hover_text("36 mm for full frame");
+    });
+
+    data_row(ui, "focal length", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut physical.focal_length)
+                .clamp_range::<f32>(1.0..=2000.)
+                .speed(0.5)
+                .suffix(" mm"),
+        );
+    });
+
+    data_row(ui, "f-number", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut physical.f_number)
+                .clamp_range::<f32>(0.5..=64.)
+                .speed(0.05)
+                .prefix("f/"),
+        );
+    });
+
+    data_row(ui, "shutter speed", |ui| {
+        // shown as a fraction, like on a camera
+        let mut denominator = 1. / physical.shutter_speed;
+        if ui
+            .add(
+                egui::DragValue::new(&mut denominator)
+                    .clamp_range::<f32>(0.001..=8000.)
+                    .speed(1.)
+                    .prefix("1/")
+                    .suffix(" s"),
+            )
+            .changed()
+        {
+            physical.shutter_speed = 1. / denominator;
+        }
+    });
+
+    data_row(ui, "ISO", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut physical.iso)
+                .clamp_range::<f32>(25.0..=102_400.)
+                .speed(10.),
+        );
+    });
+
+    data_row(ui, "focus distance", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut physical.focus_distance)
+                .clamp_range::<f32>(0.01..=10_000.)
+                .speed(0.05)
+                .suffix(" m"),
+        );
+    });
+
+    ui.label(format!(
+        "fov {:.1}°, EV100 {:.1}",
+        physical.fov(),
+        physical.ev100()
+    ));
+}
+
+fn motion_blur_settings(ui: &mut egui::Ui, scene: &mut Scene) {
+    let motion_blur = &mut scene.motion_blur;
+
+    data_row(ui, "motion blur", |ui| {
+        ui.checkbox(&mut motion_blur.enabled, "")
+            .on_hover_text("Blurs animated objects and cameras over the time the shutter is open");
+    });
+
+    if motion_blur.enabled {
+        data_row(ui, "shutter", |ui| {
+            ui.horizontal(|ui| {
+                // the physical camera sets the shutter
+                ui.set_enabled(scene.physical_camera.is_none());
+
+                ui.add(
+                    egui::DragValue::new(&mut motion_blur.shutter_open)
+                        .clamp_range::<f32>(-1.0..=motion_blur.shutter_close)
+                        .speed(0.001)
+                        .suffix(" s"),
+                )
+                .on_hover_text("When the shutter opens, after the animation time");
+                ui.add(
+                    egui::DragValue::new(&mut motion_blur.shutter_close)
+                        .clamp_range::<f32>(motion_blur.shutter_open..=1.)
+                        .speed(0.001)
+                        .suffix(" s"),
+                )
+                .on_hover_text("When the shutter closes, after the animation time");
+            });
+        });
+    }
+}
+
+/// Format a duration as minutes and seconds.
+fn format_duration(duration: std::time::Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// What a pass is, shown on the settings which count them.
+const PASSES_HOVER: &str = "One pass takes a sample of every pixel, \
+    or with adaptive sampling from none to the most samples of each pixel";
+
+fn budget_settings(ui: &mut egui::Ui, settings: &mut Settings, progress: &Progress) {
+    let budget = &mut settings.budget;
+
+    ui.heading("Budget");
+
+    data_row(ui, "pass limit", |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut budget.limit_passes, "");
+            ui.add_enabled(
+                budget.limit_passes,
+                egui::DragValue::new(&mut budget.passes)
+                    .clamp_range(1..=1_000_000)
+                    .suffix(" passes"),
+            )
+            .on_hover_text(PASSES_HOVER);
+        });
+    });
+    data_row(ui, "time limit", |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut budget.limit_time, "");
+            ui.add_enabled(
+                budget.limit_time,
+                egui::DragValue::new(&mut budget.seconds)
+                    .clamp_range::<f32>(1.0..=86_400.)
+                    .suffix(" s"),
+            );
+        });
     });
 
-    data_row(ui, "fov", |ui| {
-        ui.add(egui::DragValue::new(&mut scene.camera.fov).clamp_range::<f64>(1.0..=90.));
+    let eta = progress
+        .eta(budget)
+        .map_or_else(|| "-".to_string(), format_duration);
+    let text = format!(
+        "{} passes, {} elapsed, {eta} left",
+        progress.passes,
+        format_duration(progress.elapsed),
+    );
+
+    match progress.fraction(budget) {
+        Some(fraction) => {
+            ui.add(egui::ProgressBar::new(fraction.min(1.)).text(text));
+        }
+        None => {
+            ui.label(text);
+        }
+    }
+}
+
+fn output_settings(ui: &mut egui::Ui, settings: &mut Settings) {
+    ui.heading("Output");
+
+    data_row(ui, "AOVs", |ui| {
+        ui.checkbox(&mut settings.aovs, "")
+            .on_hover_text("Write object index, emission, direct and indirect light");
+    });
+
+    data_row(ui, "view", |ui| {
+        egui::ComboBox::from_id_source("view")
+            .selected_text(settings.display.view.name())
+            .show_ui(ui, |ui| {
+                for aov in Aov::ALL {
+                    if ui
+                        .selectable_value(&mut settings.display.view, aov, aov.name())
+                        .clicked()
+                        && aov.is_optional()
+                    {
+                        settings.aovs = true;
+                    }
+                }
+            });
+    });
+
+    data_row(ui, "exposure", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut settings.display.exposure)
+                .clamp_range::<f32>(0.0..=1000.)
+                .speed(0.01)
+                .max_decimals(6),
+        )
+        .on_hover_text("Set by the physical camera when the scene has one");
+    });
+
+    if settings.display.view == Aov::Depth {
+        data_row(ui, "depth range", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut settings.display.depth_range)
+                    .clamp_range::<f32>(0.1..=100_000.)
+                    .speed(0.5),
+            );
+        });
+    }
+
+    data_row(ui, "export to", |ui| {
+        ui.text_edit_singleline(&mut settings.export_path);
+    });
+
+    if ui
+        .button("Export EXR")
+        .on_hover_text("Writes the beauty render and each AOV to {export to}_{aov}.exr")
+        .clicked()
+    {
+        settings.request = Some(Request::Export);
+    }
+}
+
+fn sequence_settings(ui: &mut egui::Ui, settings: &mut Settings, progress: &Progress) {
+    let sequence = &mut settings.sequence;
+
+    ui.heading("Animation render");
+
+    data_row(ui, "frames", |ui| {
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut sequence.first_frame).clamp_range(1..=100_000));
+            ui.add(
+                egui::DragValue::new(&mut sequence.last_frame)
+                    .clamp_range(sequence.first_frame..=100_000),
+            );
+        });
+    });
+    data_row(ui, "frame rate", |ui| {
+        ui.add(
+            egui::DragValue::new(&mut sequence.frame_rate)
+                .clamp_range::<f32>(1.0..=240.)
+                .suffix(" fps"),
+        );
+    });
+    data_row(ui, "passes", |ui| {
+        ui.add(egui::DragValue::new(&mut sequence.passes).clamp_range(1..=1_000_000))
+            .on_hover_text(PASSES_HOVER);
+    });
+    data_row(ui, "format", |ui| {
+        egui::ComboBox::from_id_source("frame format")
+            .selected_text(sequence.format.extension())
+            .show_ui(ui, |ui| {
+                for format in FrameFormat::ALL {
+                    ui.selectable_value(&mut sequence.format, format, format.extension());
+                }
+            });
+    });
+    data_row(ui, "save to", |ui| {
+        ui.text_edit_singleline(&mut sequence.directory);
+    });
+
+    match progress.frames {
+        Some((finished, total)) => {
+            ui.add(
+                egui::ProgressBar::new(finished as f32 / total.max(1) as f32)
+                    .text(format!("{finished} / {total} frames")),
+            );
+            if ui.button("Cancel").clicked() {
+                settings.request = Some(Request::CancelSequence);
+            }
+        }
+        None => {
+            if ui
+                .add_enabled(
+                    progress.tiles.is_none(),
+                    egui::Button::new("Render animation"),
+                )
+                .on_hover_text(
+                    "Renders each frame to {save to}/frame_0001.{format}, \
+                    skipping frames which were already saved",
+                )
+                .clicked()
+            {
+                settings.request = Some(Request::RenderSequence);
+            }
+        }
+    }
+}
+
+fn tiled_settings(ui: &mut egui::Ui, settings: &mut Settings, progress: &Progress) {
+    let tiles = &mut settings.tiles;
+
+    ui.heading("Tiled render");
+
+    data_row(ui, "size", |ui| {
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut tiles.size.0).clamp_range(1..=65_536));
+            ui.add(egui::DragValue::new(&mut tiles.size.1).clamp_range(1..=65_536));
+        });
+    });
+    data_row(ui, "tile size", |ui| {
+        ui.add(egui::DragValue::new(&mut tiles.tile_size).clamp_range(16..=8192))
+            .on_hover_text("Limited to the largest texture and buffer the GPU supports");
+    });
+    data_row(ui, "passes", |ui| {
+        ui.add(egui::DragValue::new(&mut tiles.passes).clamp_range(1..=1_000_000))
+            .on_hover_text(PASSES_HOVER);
+    });
+    data_row(ui, "save to", |ui| {
+        ui.text_edit_singleline(&mut tiles.path);
+    });
+
+    match progress.tiles {
+        Some((finished, total)) => {
+            ui.add(
+                egui::ProgressBar::new(finished as f32 / total as f32)
+                    .text(format!("{finished} / {total} tiles")),
+            );
+            if ui.button("Cancel").clicked() {
+                settings.request = Some(Request::CancelTiledRender);
+            }
+        }
+        None => {
+            if ui
+                .add_enabled(progress.frames.is_none(), egui::Button::new("Render tiles"))
+                .on_hover_text("Renders the image a tile at a time and saves it when finished")
+                .clicked()
+            {
+                settings.request = Some(Request::TiledRender);
+            }
+        }
+    }
+}
+
+fn adaptive_settings(ui: &mut egui::Ui, settings: &mut Settings) {
+    let adaptive = &mut settings.adaptive;
+
+    ui.heading("Adaptive sampling");
+
+    ui.checkbox(&mut adaptive.enabled, "Adaptive")
+        .on_hover_text("Stop tracing converged pixels and take more samples of noisy ones");
+
+    ui.add_enabled_ui(adaptive.enabled, |ui| {
+        data_row(ui, "threshold", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut adaptive.threshold)
+                    .clamp_range::<f32>(0.001..=1.)
+                    .speed(0.001),
+            )
+            .on_hover_text("The relative error below which a pixel has converged");
+        });
+        data_row(ui, "min samples", |ui| {
+            ui.add(egui::DragValue::new(&mut adaptive.min_samples).clamp_range(2..=1024));
+        });
+        data_row(ui, "max per frame", |ui| {
+            ui.add(egui::Slider::new(&mut adaptive.max_samples, 1..=16));
+        });
+    });
+}
+
+fn convergence_plot(ui: &mut egui::Ui, settings: &mut Settings, progress: &Progress) {
+    let convergence = &progress.convergence;
+
+    ui.heading("Convergence");
+
+    if ui
+        .button("Use as reference")
+        .on_hover_text(
+            "Render with many samples, then use it as the reference \
+            to measure each sampler's error against",
+        )
+        .clicked()
+    {
+        settings.request = Some(Request::Reference);
+    }
+
+    if !convergence.has_reference() {
+        return;
+    }
+
+    let lines = convergence
+        .curves
+        .iter()
+        .map(|(sampler, points)| {
+            let points = points
+                .iter()
+                .map(|(samples, rmse)| [f64::from(*samples).log2(), f64::from(*rmse).log10()])
+                .collect::<Vec<_>>();
+            egui_plot::Line::new(points).name(sampler.name())
+        })
+        .collect::<Vec<_>>();
+
+    egui_plot::Plot::new("Convergence")
+        .x_axis_label("log2 samples per pixel")
+        .y_axis_label("log10 RMSE")
+        .height(300.)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .legend(egui_plot::Legend::default())
+        .show(ui, |ui| {
+            for line in lines {
+                ui.line(line);
+            }
+        });
+}
+
+fn denoise_settings(ui: &mut egui::Ui, settings: &mut Settings) {
+    let denoise = &mut settings.denoise;
+
+    ui.heading("Denoiser");
+
+    ui.checkbox(&mut denoise.enabled, "Denoise");
+
+    ui.add_enabled_ui(denoise.enabled, |ui| {
+        data_row(ui, "iterations", |ui| {
+            ui.add(egui::Slider::new(
+                &mut denoise.iterations,
+                1..=Denoiser::MAX_ITERATIONS,
+            ));
+        });
+        data_row(ui, "colour", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut denoise.colour_phi)
+                    .clamp_range::<f32>(0.001..=100.)
+                    .speed(0.01),
+            );
+        });
+        data_row(ui, "normal", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut denoise.normal_phi)
+                    .clamp_range::<f32>(1.0..=256.)
+                    .speed(1.),
+            );
+        });
+        data_row(ui, "depth", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut denoise.depth_phi)
+                    .clamp_range::<f32>(0.001..=10.)
+                    .speed(0.01),
+            );
+        });
+        data_row(ui, "albedo", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut denoise.albedo_phi)
+                    .clamp_range::<f32>(0.001..=10.)
+                    .speed(0.01),
+            );
+        });
+    });
+}
+
+fn bloom_settings(ui: &mut egui::Ui, settings: &mut Settings) {
+    let bloom = &mut settings.display.bloom;
+
+    ui.heading("Bloom");
+
+    ui.checkbox(&mut bloom.enabled, "Bloom")
+        .on_hover_text("Only added to the displayed beauty, exports are left untouched");
+
+    ui.add_enabled_ui(bloom.enabled, |ui| {
+        data_row(ui, "threshold", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut bloom.threshold)
+                    .clamp_range::<f32>(0.0..=100.)
+                    .speed(0.01),
+            );
+        });
+        data_row(ui, "intensity", |ui| {
+            ui.add(
+                egui::DragValue::new(&mut bloom.intensity)
+                    .clamp_range::<f32>(0.0..=10.)
+                    .speed(0.005),
+            );
+        });
+        data_row(ui, "radius", |ui| {
+            ui.add(egui::Slider::new(&mut bloom.radius, 0.0..=1.));
+        });
     });
 }
@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+use crate::convergence::Convergence;
+use crate::statistics::Statistics;
+
+/// Limits on how long a render accumulates before it is final.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BudgetSettings {
+    /// Whether the render stops after `passes`.
+    pub limit_passes: bool,
+    /// The passes over the image to stop after,
+    /// which are samples per pixel unless sampling adaptively.
+    pub passes: u32,
+    /// Whether the render stops after `seconds`.
+    pub limit_time: bool,
+    /// The seconds of rendering to stop after.
+    pub seconds: f32,
+}
+
+impl Default for BudgetSettings {
+    fn default() -> Self {
+        Self {
+            limit_passes: false,
+            passes: 1024,
+            limit_time: false,
+            seconds: 60.,
+        }
+    }
+}
+
+/// How far the current render has accumulated.
+pub struct Progress {
+    /// Passes over the image accumulated,
+    /// each taking from 0 to the most samples per pixel when sampling adaptively.
+    pub passes: u32,
+    /// Time spent rendering, not counting time paused after reaching the budget.
+    pub elapsed: Duration,
+    last_sample: Option<Instant>,
+    /// Kept across renders, to compare them.
+    pub convergence: Convergence,
+    /// The finished and total tiles, while rendering in tiles.
+    pub tiles: Option<(usize, usize)>,
+    /// The finished and total frames, while rendering the animation.
+    pub frames: Option<(u32, u32)>,
+    /// Kept across renders, to show how fast they accumulate.
+    pub statistics: Statistics,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Progress {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            passes: 0,
+            elapsed: Duration::ZERO,
+            last_sample: Some(Instant::now()),
+            convergence: Convergence::default(),
+            tiles: None,
+            frames: None,
+            statistics: Statistics::default(),
+        }
+    }
+
+    /// Start counting again, as the render was restarted.
+    pub fn restart(&mut self) {
+        self.passes = 0;
+        self.statistics.restart();
+        self.elapsed = Duration::ZERO;
+        self.last_sample = Some(Instant::now());
+    }
+
+    /// Count a pass over the image.
+    pub fn add_pass(&mut self) {
+        let now = Instant::now();
+        if let Some(last_sample) = self.last_sample {
+            self.elapsed += now - last_sample;
+        }
+        self.last_sample = Some(now);
+        self.passes += 1;
+    }
+
+    /// Stop the clock, as no samples are being taken.
+    pub const fn pause(&mut self) {
+        self.last_sample = None;
+    }
+
+    /// Whether the render has used up its budget.
+    #[must_use]
+    pub fn is_finished(&self, budget: &BudgetSettings) -> bool {
+        self.fraction(budget).is_some_and(|fraction| fraction >= 1.)
+    }
+
+    /// How much of the budget has been used, if there is one.
+    #[must_use]
+    pub fn fraction(&self, budget: &BudgetSettings) -> Option<f32> {
+        let passes = budget
+            .limit_passes
+            .then(|| self.passes as f32 / budget.passes.max(1) as f32);
+        let time = budget
+            .limit_time
+            .then(|| self.elapsed.as_secs_f32() / budget.seconds.max(0.001));
+
+        match (passes, time) {
+            (Some(passes), Some(time)) => Some(passes.max(time)),
+            (passes, time) => passes.or(time),
+        }
+    }
+
+    /// The estimated time until the budget is used up, if there is one.
+    #[must_use]
+    pub fn eta(&self, budget: &BudgetSettings) -> Option<Duration> {
+        let fraction = self.fraction(budget)?;
+
+        if fraction >= 1. {
+            return Some(Duration::ZERO);
+        }
+        if fraction <= 0. {
+            return None;
+        }
+
+        Some(self.elapsed.mul_f32((1. - fraction) / fraction))
+    }
+}
@@ -0,0 +1,655 @@
+use nalgebra::Rotation3;
+use serde::{Deserialize, Serialize};
+
+use super::{Camera, Geometry, Light, MotionBlurSettings, Object, Scene, Vec3};
+
+/// How a track moves from one keyframe to the next.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Hold the value until the next keyframe.
+    Step,
+    /// Move in a straight line to the next keyframe.
+    Linear,
+    /// A Catmull-Rom spline through the surrounding keyframes.
+    Cubic,
+}
+
+impl Interpolation {
+    /// Every interpolation, in the order they are shown in the UI.
+    pub const ALL: [Self; 3] = [Self::Step, Self::Linear, Self::Cubic];
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Step => "Step",
+            Self::Linear => "Linear",
+            Self::Cubic => "Cubic",
+        }
+    }
+}
+
+/// What a track animates.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Target {
+    /// The scene's camera.
+    Camera,
+    /// The object with this id.
+    Object(u128),
+    /// The light at this index.
+    Light(usize),
+}
+
+/// The property of a target that a track animates.
+///
+/// Scalar properties are stored in the x component of keyframe values.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Property {
+    /// The camera's, an object's center or a point light's position.
+    Position,
+    /// The camera's euler rotation.
+    Rotation,
+    /// The camera's field of view.
+    Fov,
+    /// A sphere's radius.
+    Radius,
+    /// An object's material colour.
+    Colour,
+    /// An object's emission colour.
+    Emission,
+    /// An object's emission strength.
+    EmissionStrength,
+    /// An object's metallic.
+    Metallic,
+    /// An object's roughness.
+    Roughness,
+    /// A light's intensity.
+    Intensity,
+    /// A directional light's direction.
+    Direction,
+}
+
+impl Property {
+    /// The properties that can be animated on `target`.
+    #[must_use]
+    pub const fn of(target: Target) -> &'static [Self] {
+        match target {
+            Target::Camera => &[Self::Position, Self::Rotation, Self::Fov],
+            Target::Object(_) => &[
+                Self::Position,
+                Self::Radius,
+                Self::Colour,
+                Self::Emission,
+                Self::EmissionStrength,
+                Self::Metallic,
+                Self::Roughness,
+            ],
+            Target::Light(_) => &[Self::Position, Self::Direction, Self::Intensity],
+        }
+    }
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Position => "position",
+            Self::Rotation => "rotation",
+            Self::Fov => "fov",
+            Self::Radius => "radius",
+            Self::Colour => "colour",
+            Self::Emission => "emission",
+            Self::EmissionStrength => "emission strength",
+            Self::Metallic => "metallic",
+            Self::Roughness => "roughness",
+            Self::Intensity => "intensity",
+            Self::Direction => "direction",
+        }
+    }
+}
+
+/// A reference to an animatable value in the scene.
+enum Field<'a> {
+    Vector(&'a mut Vec3),
+    Scalar(&'a mut f32),
+    /// Keyed as a euler rotation.
+    Camera(&'a mut Camera),
+}
+
+impl Field<'_> {
+    fn get(&self) -> Vec3 {
+        match self {
+            Self::Vector(vector) => **vector,
+            Self::Scalar(scalar) => Vec3::new(**scalar, 0., 0.),
+            Self::Camera(camera) => camera.euler(),
+        }
+    }
+
+    fn set(self, value: Vec3) {
+        match self {
+            Self::Vector(vector) => *vector = value,
+            Self::Scalar(scalar) => *scalar = value.x,
+            Self::Camera(camera) => camera.set_euler(value),
+        }
+    }
+}
+
+/// Find the value a track animates,
+/// or [`None`] if the target no longer exists or doesn't have the property.
+fn field(scene: &mut Scene, target: Target, property: Property) -> Option<Field<'_>> {
+    match target {
+        Target::Camera => match property {
+            Property::Position => Some(Field::Vector(&mut scene.camera.position)),
+            Property::Rotation => Some(Field::Camera(&mut scene.camera)),
+            Property::Fov => Some(Field::Scalar(&mut scene.camera.fov)),
+            _ => None,
+        },
+        Target::Object(id) => {
+            let object = scene.objects.iter_mut().find(|object| object.id == id)?;
+            match property {
+                Property::Position => Some(Field::Vector(object.geometry.position_as_mut())),
+                Property::Radius => match &mut object.geometry {
+                    Geometry::Sphere { radius, .. } => Some(Field::Scalar(radius)),
+                    _ => None,
+                },
+                Property::Colour => Some(Field::Vector(&mut object.material.colour)),
+                Property::Emission => Some(Field::Vector(&mut object.material.emission)),
+                Property::EmissionStrength => {
+                    Some(Field::Scalar(&mut object.material.emission_strength))
+                }
+                Property::Metallic => Some(Field::Scalar(&mut object.material.metallic)),
+                Property::Roughness => Some(Field::Scalar(&mut object.material.roughness)),
+                _ => None,
+            }
+        }
+        Target::Light(index) => match (scene.lights.get_mut(index)?, property) {
+            (Light::Point { position, .. }, Property::Position) => Some(Field::Vector(position)),
+            (Light::Direction { direction, .. }, Property::Direction) => {
+                Some(Field::Vector(direction))
+            }
+            (
+                Light::Point { intensity, .. } | Light::Direction { intensity, .. },
+                Property::Intensity,
+            ) => Some(Field::Vector(intensity)),
+            _ => None,
+        },
+    }
+}
+
+/// The value of a track at a point in time.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// The time in seconds.
+    pub time: f32,
+    /// The value keyed, with scalars in x.
+    pub value: Vec3,
+    /// How the track moves from this keyframe to the next.
+    pub interpolation: Interpolation,
+}
+
+/// The keyframes of one property of one target.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Track {
+    /// What is animated.
+    pub target: Target,
+    /// Which of its properties is animated.
+    pub property: Property,
+    /// Sorted by time.
+    pub keys: Vec<Keyframe>,
+}
+
+impl Track {
+    /// The value of the track at `time`,
+    /// holding the first and last keyframes before and after them.
+    #[must_use]
+    pub fn sample(&self, time: f32) -> Option<Vec3> {
+        let Some(next) = self.keys.iter().position(|key| key.time > time) else {
+            return self.keys.last().map(|key| key.value);
+        };
+        if next == 0 {
+            return Some(self.keys[0].value);
+        }
+
+        let start = &self.keys[next - 1];
+        let end = &self.keys[next];
+        let t = (time - start.time) / (end.time - start.time);
+
+        Some(match start.interpolation {
+            Interpolation::Step => start.value,
+            Interpolation::Linear => start.value.lerp(&end.value, t),
+            Interpolation::Cubic => {
+                let before = self.keys[next.saturating_sub(2)].value;
+                let after = self.keys.get(next + 1).map_or(end.value, |key| key.value);
+                catmull_rom(before, start.value, end.value, after, t)
+            }
+        })
+    }
+}
+
+/// A point on the Catmull-Rom spline between `p1` and `p2`.
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    0.5 * (2. * p1
+        + (p2 - p0) * t
+        + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t2
+        + (3. * p1 - p0 - 3. * p2 + p3) * t3)
+}
+
+/// A timeline of keyframed tracks, saved with the scene.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Animation {
+    /// At most one for each property of each target.
+    pub tracks: Vec<Track>,
+    /// The length of the timeline in seconds.
+    pub duration: f32,
+    /// Whether playing wraps around to the start.
+    pub looping: bool,
+    /// The current time in seconds.
+    #[serde(skip)]
+    pub time: f32,
+    /// Whether the time is advancing.
+    #[serde(skip)]
+    pub playing: bool,
+    /// The time the scene was last set to,
+    /// so the tracks only overwrite edits when the time changes.
+    #[serde(skip)]
+    applied_time: Option<f32>,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            tracks: Vec::new(),
+            duration: 10.,
+            looping: true,
+            time: 0.,
+            playing: false,
+            applied_time: None,
+        }
+    }
+}
+
+impl Animation {
+    /// The seconds each sphere takes to go around the origin in [`Self::spin`].
+    const SPIN_PERIOD: f32 = 4.;
+    /// The keyframes per revolution in [`Self::spin`].
+    const SPIN_KEYS: usize = 16;
+
+    /// An animation spinning every sphere around the vertical axis through the origin.
+    #[must_use]
+    pub fn spin(objects: &[Object]) -> Self {
+        let tracks = objects
+            .iter()
+            .filter(|object| matches!(object.geometry, Geometry::Sphere { .. }))
+            .map(|object| Track {
+                target: Target::Object(object.id),
+                property: Property::Position,
+                keys: (0..=Self::SPIN_KEYS)
+                    .map(|i| {
+                        let fraction = i as f32 / Self::SPIN_KEYS as f32;
+                        let rotation =
+                            Rotation3::from_euler_angles(0., fraction * std::f32::consts::TAU, 0.);
+
+                        Keyframe {
+                            time: fraction * Self::SPIN_PERIOD,
+                            value: rotation * object.geometry.position(),
+                            interpolation: Interpolation::Cubic,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            tracks,
+            duration: Self::SPIN_PERIOD,
+            playing: true,
+            ..Default::default()
+        }
+    }
+
+    /// Move the time forward while playing.
+    pub fn advance(&mut self, delta_time: f32) {
+        if !self.playing {
+            return;
+        }
+
+        self.time += delta_time;
+        if self.time >= self.duration {
+            if self.looping {
+                self.time = self.time.rem_euclid(self.duration.max(f32::EPSILON));
+            } else {
+                self.time = self.duration;
+                self.playing = false;
+            }
+        }
+    }
+
+    /// Whether a track animates this property.
+    #[must_use]
+    pub fn is_animated(&self, target: Target, property: Property) -> bool {
+        self.tracks
+            .iter()
+            .any(|track| track.target == target && track.property == property)
+    }
+
+    /// Sort every track's keyframes by time, as [`Track::sample`] expects.
+    pub fn sort_keys(&mut self) {
+        for track in &mut self.tracks {
+            track.keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+    }
+
+    /// Key the value at the current time,
+    /// replacing any keyframe already there.
+    pub fn set_key(
+        &mut self,
+        target: Target,
+        property: Property,
+        value: Vec3,
+        interpolation: Interpolation,
+    ) {
+        let index = self
+            .tracks
+            .iter()
+            .position(|track| track.target == target && track.property == property)
+            .unwrap_or_else(|| {
+                self.tracks.push(Track {
+                    target,
+                    property,
+                    keys: Vec::new(),
+                });
+                self.tracks.len() - 1
+            });
+        let keys = &mut self.tracks[index].keys;
+
+        keys.retain(|key| (key.time - self.time).abs() > 1e-3);
+        let position = keys.partition_point(|key| key.time < self.time);
+        keys.insert(
+            position,
+            Keyframe {
+                time: self.time,
+                value,
+                interpolation,
+            },
+        );
+    }
+}
+
+impl Scene {
+    /// Advance the animation and set the animated values,
+    /// if the time has changed since they were last set.
+    pub fn animate(&mut self, delta_time: f32) {
+        puffin::profile_function!();
+
+        self.animation.advance(delta_time);
+        if self.animation.applied_time == Some(self.animation.time) {
+            return;
+        }
+
+        let animation = std::mem::take(&mut self.animation);
+        for track in &animation.tracks {
+            if let (Some(value), Some(field)) = (
+                track.sample(animation.time),
+                field(self, track.target, track.property),
+            ) {
+                field.set(value);
+            }
+        }
+
+        self.animation = animation;
+        self.animation.applied_time = Some(self.animation.time);
+    }
+
+    /// A copy of the scene at another animation time.
+    fn at_time(&self, time: f32) -> Self {
+        let mut scene = self.clone();
+        let animation = &mut scene.animation;
+
+        animation.playing = false;
+        animation.time = if animation.looping {
+            time.rem_euclid(animation.duration.max(f32::EPSILON))
+        } else {
+            time
+        };
+        scene.animate(0.);
+
+        scene
+    }
+
+    /// Set how the camera and objects move while the shutter is open,
+    /// from where the animation has them as it opens and closes.
+    pub fn update_motion(&mut self) {
+        puffin::profile_function!();
+
+        let MotionBlurSettings {
+            enabled,
+            shutter_open,
+            shutter_close,
+        } = self.motion_blur;
+
+        if !enabled || self.animation.tracks.is_empty() || shutter_close <= shutter_open {
+            self.camera_shutter = None;
+            for object in &mut self.objects {
+                object.velocity = Vec3::zeros();
+            }
+            return;
+        }
+
+        let open = self.at_time(self.animation.time + shutter_open);
+        let close = self.at_time(self.animation.time + shutter_close);
+
+        for ((object, start), end) in self
+            .objects
+            .iter_mut()
+            .zip(&open.objects)
+            .zip(&close.objects)
+        {
+            object.velocity = (end.geometry.position() - start.geometry.position())
+                / (shutter_close - shutter_open);
+        }
+
+        self.camera_shutter = Some([open.camera, close.camera]);
+    }
+
+    /// The current value of an animatable property,
+    /// or [`None`] if the target doesn't have it.
+    pub fn animated_value(&mut self, target: Target, property: Property) -> Option<Vec3> {
+        field(self, target, property).map(|field| field.get())
+    }
+
+    /// Key the current value of a property at the current time.
+    pub fn set_key(&mut self, target: Target, property: Property, interpolation: Interpolation) {
+        if let Some(value) = self.animated_value(target, property) {
+            self.animation
+                .set_key(target, property, value, interpolation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, x: f32, interpolation: Interpolation) -> Keyframe {
+        Keyframe {
+            time,
+            value: Vec3::new(x, 0., 0.),
+            interpolation,
+        }
+    }
+
+    fn track(keys: Vec<Keyframe>) -> Track {
+        Track {
+            target: Target::Camera,
+            property: Property::Fov,
+            keys,
+        }
+    }
+
+    fn sample_x(track: &Track, time: f32) -> Option<f32> {
+        track.sample(time).map(|value| value.x)
+    }
+
+    #[test]
+    fn empty_track_has_no_value() {
+        assert_eq!(track(Vec::new()).sample(1.), None);
+    }
+
+    #[test]
+    fn holds_first_and_last_keys() {
+        let track = track(vec![
+            key(1., 10., Interpolation::Linear),
+            key(2., 20., Interpolation::Linear),
+        ]);
+
+        assert_eq!(sample_x(&track, 0.), Some(10.));
+        assert_eq!(sample_x(&track, 3.), Some(20.));
+    }
+
+    #[test]
+    fn step_holds_until_next_key() {
+        let track = track(vec![
+            key(0., 10., Interpolation::Step),
+            key(1., 20., Interpolation::Step),
+        ]);
+
+        assert_eq!(sample_x(&track, 0.), Some(10.));
+        assert_eq!(sample_x(&track, 0.99), Some(10.));
+        assert_eq!(sample_x(&track, 1.), Some(20.));
+    }
+
+    #[test]
+    fn linear_interpolates_between_keys() {
+        let track = track(vec![
+            key(0., 10., Interpolation::Linear),
+            key(2., 20., Interpolation::Linear),
+        ]);
+
+        assert_eq!(sample_x(&track, 0.), Some(10.));
+        assert_eq!(sample_x(&track, 0.5), Some(12.5));
+        assert_eq!(sample_x(&track, 2.), Some(20.));
+    }
+
+    #[test]
+    fn cubic_passes_through_keys() {
+        let track = track(vec![
+            key(0., 0., Interpolation::Cubic),
+            key(1., 10., Interpolation::Cubic),
+            key(2., 0., Interpolation::Cubic),
+            key(3., 10., Interpolation::Cubic),
+        ]);
+
+        for (time, x) in [(0., 0.), (1., 10.), (2., 0.), (3., 10.)] {
+            assert_eq!(sample_x(&track, time), Some(x));
+        }
+        // symmetric about the middle of the segment
+        assert_eq!(sample_x(&track, 1.5), Some(5.));
+    }
+
+    #[test]
+    fn cubic_on_evenly_spaced_line_is_linear() {
+        let p = |x: f32| Vec3::new(x, 0., 0.);
+        let point = catmull_rom(p(0.), p(1.), p(2.), p(3.), 0.25);
+
+        assert!((point.x - 1.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn catmull_rom_reaches_the_middle_points() {
+        let p0 = Vec3::new(-1., 4., 0.);
+        let p1 = Vec3::new(0., 1., 2.);
+        let p2 = Vec3::new(3., -2., 1.);
+        let p3 = Vec3::new(5., 0., -1.);
+
+        assert_eq!(catmull_rom(p0, p1, p2, p3, 0.), p1);
+        assert_eq!(catmull_rom(p0, p1, p2, p3, 1.), p2);
+    }
+
+    fn key_times(animation: &Animation) -> Vec<f32> {
+        animation.tracks[0]
+            .keys
+            .iter()
+            .map(|key| key.time)
+            .collect()
+    }
+
+    #[test]
+    fn set_key_keeps_keys_sorted() {
+        let mut animation = Animation::default();
+        for time in [2., 0., 1.] {
+            animation.time = time;
+            animation.set_key(
+                Target::Camera,
+                Property::Fov,
+                Vec3::new(time, 0., 0.),
+                Interpolation::Linear,
+            );
+        }
+
+        assert_eq!(animation.tracks.len(), 1);
+        assert_eq!(key_times(&animation), vec![0., 1., 2.]);
+    }
+
+    #[test]
+    fn set_key_replaces_key_at_same_time() {
+        let mut animation = Animation {
+            time: 1.,
+            ..Default::default()
+        };
+        animation.set_key(
+            Target::Camera,
+            Property::Fov,
+            Vec3::new(10., 0., 0.),
+            Interpolation::Linear,
+        );
+        animation.time = 1.0001;
+        animation.set_key(
+            Target::Camera,
+            Property::Fov,
+            Vec3::new(20., 0., 0.),
+            Interpolation::Step,
+        );
+
+        let keys = &animation.tracks[0].keys;
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].value, Vec3::new(20., 0., 0.));
+        assert_eq!(keys[0].interpolation, Interpolation::Step);
+    }
+
+    #[test]
+    fn set_key_adds_a_track_per_property() {
+        let mut animation = Animation::default();
+        animation.set_key(
+            Target::Camera,
+            Property::Fov,
+            Vec3::zeros(),
+            Interpolation::Linear,
+        );
+        animation.set_key(
+            Target::Camera,
+            Property::Position,
+            Vec3::zeros(),
+            Interpolation::Linear,
+        );
+
+        assert_eq!(animation.tracks.len(), 2);
+    }
+
+    #[test]
+    fn sort_keys_orders_by_time() {
+        let mut animation = Animation {
+            tracks: vec![track(vec![
+                key(2., 0., Interpolation::Linear),
+                key(0., 0., Interpolation::Linear),
+                key(1., 0., Interpolation::Linear),
+            ])],
+            ..Default::default()
+        };
+        animation.sort_keys();
+
+        assert_eq!(key_times(&animation), vec![0., 1., 2.]);
+    }
+}
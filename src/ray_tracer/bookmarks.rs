@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Camera, Scene};
+
+/// A saved camera view.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// The name shown in the objects panel.
+    pub name: String,
+    /// The camera as it was when saved.
+    pub camera: Camera,
+}
+
+/// A move from one view to another.
+struct Transition {
+    from: Camera,
+    to: Camera,
+    /// Seconds since the transition started.
+    elapsed: f32,
+}
+
+/// Moves the camera to bookmarked views, either instantly or smoothly.
+pub struct Views {
+    /// Whether the camera moves smoothly to the view, instead of jumping there.
+    pub smooth: bool,
+    /// The seconds a smooth move takes.
+    pub duration: f32,
+    transition: Option<Transition>,
+}
+
+impl Default for Views {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Views {
+    /// Smooth one second moves, with no move in progress.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            smooth: true,
+            duration: 1.,
+            transition: None,
+        }
+    }
+
+    /// Move the camera to the bookmark at `index`, if there is one.
+    pub fn go_to(&mut self, scene: &mut Scene, index: usize) {
+        let Some(bookmark) = scene.bookmarks.get(index) else {
+            return;
+        };
+
+        if self.smooth {
+            self.transition = Some(Transition {
+                from: scene.camera.clone(),
+                to: bookmark.camera.clone(),
+                elapsed: 0.,
+            });
+        } else {
+            self.transition = None;
+            scene.camera = bookmark.camera.clone();
+        }
+    }
+
+    /// Move the camera along the current transition.
+    pub fn update(&mut self, scene: &mut Scene, delta_time: f32) {
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+
+        transition.elapsed += delta_time;
+        let t = (transition.elapsed / self.duration.max(f32::EPSILON)).min(1.);
+        // ease in and out
+        let t = t * t * 2f32.mul_add(-t, 3.);
+
+        scene.camera = transition.from.lerp(&transition.to, t);
+
+        if t >= 1. {
+            self.transition = None;
+        }
+    }
+}
@@ -1,67 +1,422 @@
-use nalgebra::Rotation3;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use nalgebra::UnitQuaternion;
+use serde::{Deserialize, Serialize};
 
 use super::Vec3;
 
 /// Stores information about the camera in a scene.
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedCamera")]
 pub struct Camera {
     /// The position of the camera.
     pub position: Vec3,
-    /// The rotation of the camera.
-    ///
-    /// This is a euler rotation in radians.
-    pub rotation: Vec3,
+    /// Turns the camera's local axes into world space,
+    /// where +X is right, +Y is up and the camera looks down -Z.
+    pub orientation: UnitQuaternion<f32>,
     /// The fov of the camera in degrees.
+    ///
+    /// For fisheyes this is the angle across the width of the image, and can be up to 360.
     pub fov: f32,
+    /// How rays spread out from the camera across the image.
+    pub projection: Projection,
+    /// Whether both eyes are rendered, and how far apart they are.
+    pub stereo: Stereo,
+}
+
+/// How the camera's rays spread out across the image.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum Projection {
+    /// Rays spread out from a point, as with a pinhole camera.
+    #[default]
+    Perspective,
+    /// Parallel rays, so distant objects aren't smaller.
+    Orthographic {
+        /// The width of the image in world units.
+        width: f32,
+    },
+    /// A 360° by 180° panorama, best rendered twice as wide as it is tall.
+    Equirectangular,
+    /// An equidistant fisheye, where the distance from the centre is proportional to the angle.
+    Fisheye,
+}
+
+impl Projection {
+    /// Every projection, in the order they are shown in the UI.
+    pub const ALL: [Self; 4] = [
+        Self::Perspective,
+        Self::Orthographic { width: 10. },
+        Self::Equirectangular,
+        Self::Fisheye,
+    ];
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Perspective => "Perspective",
+            Self::Orthographic { .. } => "Orthographic",
+            Self::Equirectangular => "Equirectangular",
+            Self::Fisheye => "Fisheye",
+        }
+    }
+
+    /// The index used in `create_ray` in `ray.hlsl`.
+    #[must_use]
+    pub const fn index(self) -> u32 {
+        match self {
+            Self::Perspective => 0,
+            Self::Orthographic { .. } => 1,
+            Self::Equirectangular => 2,
+            Self::Fisheye => 3,
+        }
+    }
+
+    /// The width of an orthographic image, or 0 for the other projections.
+    #[must_use]
+    pub const fn orthographic_width(self) -> f32 {
+        match self {
+            Self::Orthographic { width } => width,
+            _ => 0.,
+        }
+    }
+}
+
+/// A camera as saved, including the euler rotation saved before orientations were quaternions.
+#[derive(Deserialize)]
+struct SavedCamera {
+    position: Vec3,
+    orientation: Option<UnitQuaternion<f32>>,
+    rotation: Option<Vec3>,
+    fov: f32,
+    #[serde(default)]
+    projection: Projection,
+    #[serde(default)]
+    stereo: Stereo,
+}
+
+impl From<SavedCamera> for Camera {
+    fn from(saved: SavedCamera) -> Self {
+        Self {
+            position: saved.position,
+            orientation: saved.orientation.unwrap_or_else(|| {
+                Self::orientation_from_euler(saved.rotation.unwrap_or_default())
+            }),
+            fov: saved.fov,
+            projection: saved.projection,
+            stereo: saved.stereo,
+        }
+    }
+}
+
+/// How the image is split between the left and right eyes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum StereoLayout {
+    /// A single image, from between the eyes.
+    #[default]
+    Off,
+    /// The left eye on the left half and the right eye on the right.
+    SideBySide,
+    /// The left eye on the top half and the right eye on the bottom.
+    TopBottom,
+}
+
+impl StereoLayout {
+    /// Every layout, in the order they are shown in the UI.
+    pub const ALL: [Self; 3] = [Self::Off, Self::SideBySide, Self::TopBottom];
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::SideBySide => "Side by side",
+            Self::TopBottom => "Top and bottom",
+        }
+    }
+
+    /// The index used in `create_ray` in `ray.hlsl`.
+    #[must_use]
+    pub const fn index(self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::SideBySide => 1,
+            Self::TopBottom => 2,
+        }
+    }
+}
+
+/// Rendering both eyes into one image.
+///
+/// With the equirectangular projection this is an omni-directional stereo panorama.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Stereo {
+    /// Where each eye's image goes, or off for a single image.
+    pub layout: StereoLayout,
+    /// The distance between the eyes in world units.
+    pub eye_separation: f32,
+}
+
+impl Default for Stereo {
+    fn default() -> Self {
+        Self {
+            layout: StereoLayout::Off,
+            eye_separation: 0.064,
+        }
+    }
+}
+
+impl Stereo {
+    /// The width over the height of each eye's part of an image.
+    #[must_use]
+    pub fn eye_aspect(self, aspect: f32) -> f32 {
+        match self.layout {
+            StereoLayout::Off => aspect,
+            StereoLayout::SideBySide => aspect / 2.,
+            StereoLayout::TopBottom => aspect * 2.,
+        }
+    }
+
+    /// Which eye `coord` on the image is seen by, -1 for the left, 1 for the right or 0 without stereo,
+    /// along with where it is in that eye's part of the image, and its aspect ratio.
+    #[must_use]
+    pub fn eye(self, coord: (f32, f32), aspect: f32) -> (f32, (f32, f32), f32) {
+        let side = |position: f32| if position < 0.5 { -1. } else { 1. };
+        let eye_aspect = self.eye_aspect(aspect);
+
+        match self.layout {
+            StereoLayout::Off => (0., coord, aspect),
+            StereoLayout::SideBySide => {
+                (side(coord.0), ((coord.0 * 2.).fract(), coord.1), eye_aspect)
+            }
+            StereoLayout::TopBottom => {
+                (side(coord.1), (coord.0, (coord.1 * 2.).fract()), eye_aspect)
+            }
+        }
+    }
+
+    /// Where `coord` in the left eye's part of the image is on the whole image.
+    #[must_use]
+    pub fn left_eye_to_image(self, coord: (f32, f32)) -> (f32, f32) {
+        match self.layout {
+            StereoLayout::Off => coord,
+            StereoLayout::SideBySide => (coord.0 / 2., coord.1),
+            StereoLayout::TopBottom => (coord.0, coord.1 / 2.),
+        }
+    }
 }
 
 impl Camera {
-    /// Clamps / wraps the rotation to within these limits.
-    /// - x should be clamped between -pi/2 and pi/2
-    /// - y should be wrapped around to between -pi and pi
-    /// - z should be clamped between -pi and pi
+    /// The orientation from a euler rotation in radians,
+    /// where x looks down, y turns right and z rolls.
+    #[must_use]
+    pub fn orientation_from_euler(rotation: Vec3) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vec3::y_axis(), -rotation.y)
+            * UnitQuaternion::from_axis_angle(&Vec3::x_axis(), -rotation.x)
+            * UnitQuaternion::from_axis_angle(&Vec3::z_axis(), -rotation.z)
+    }
+
+    /// The orientation as a euler rotation in radians, as in [`Camera::orientation_from_euler`].
     ///
-    /// This should be called after any manipulation of the rotation.
-    pub fn clamp_rotation(&mut self) {
-        puffin::profile_function!();
+    /// Looking straight up or down, the turn and roll can't be told apart, so the roll is 0.
+    #[must_use]
+    pub fn euler(&self) -> Vec3 {
+        let (forward, right, _) = self.get_vectors_fru();
+
+        let x = forward.y.clamp(-1., 1.).asin();
+        let y = (-forward.x).atan2(forward.z);
 
-        // x should be clamped between -pi/2 and pi/2
-        self.rotation.x = self.rotation.x.clamp(
-            (-0.5f32).mul_add(std::f32::consts::PI, 0.01),
-            0.5f32.mul_add(std::f32::consts::PI, -0.01),
-        );
+        // the axes before rolling
+        let unrolled = Self::orientation_from_euler(Vec3::new(x, y, 0.));
+        let z = (-right.dot(&(unrolled * Vec3::y()))).atan2(right.dot(&(unrolled * Vec3::x())));
 
-        // y should be wrapped around to between -pi and pi
-        if self.rotation.y < std::f32::consts::PI {
-            self.rotation.y += 2. * std::f32::consts::PI;
+        Vec3::new(x, y, z)
+    }
+
+    /// Set the orientation from a euler rotation in radians.
+    pub fn set_euler(&mut self, rotation: Vec3) {
+        self.orientation = Self::orientation_from_euler(rotation);
+    }
+
+    /// Turn the camera to face `target`, with `up` pointing as close to up on screen as it can.
+    ///
+    /// Does nothing if the camera is at the target or looking along `up`.
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        let backward = self.position - target;
+        if backward.cross(&up).norm_squared() > f32::EPSILON {
+            self.orientation = UnitQuaternion::face_towards(&backward, &up);
         }
-        if self.rotation.y > std::f32::consts::PI {
-            self.rotation.y -= 2. * std::f32::consts::PI;
+    }
+
+    /// Turn left and right around the world's up axis, in radians.
+    pub fn turn(&mut self, angle: f32) {
+        self.orientation =
+            UnitQuaternion::from_axis_angle(&Vec3::y_axis(), -angle) * self.orientation;
+    }
+
+    /// Look up and down around the camera's right axis, in radians.
+    pub fn pitch(&mut self, angle: f32) {
+        self.orientation *= UnitQuaternion::from_axis_angle(&Vec3::x_axis(), -angle);
+    }
+
+    /// Roll around the direction the camera looks, in radians.
+    pub fn roll(&mut self, angle: f32) {
+        self.orientation *= UnitQuaternion::from_axis_angle(&Vec3::z_axis(), -angle);
+    }
+
+    /// Blend between two cameras, turning the shortest way around.
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(&other.position, t),
+            orientation: self.orientation.slerp(&other.orientation, t),
+            fov: (other.fov - self.fov).mul_add(t, self.fov),
+            projection: if t < 0.5 {
+                self.projection
+            } else {
+                other.projection
+            },
+            stereo: if t < 0.5 { self.stereo } else { other.stereo },
         }
+    }
 
-        // z should be clamped between -pi and pi
-        self.rotation.z = self
-            .rotation
-            .z
-            .clamp(-std::f32::consts::PI, std::f32::consts::PI);
+    /// The half width and half height of the image one unit in front of the camera,
+    /// where `aspect` is the width over the height.
+    fn half_size(&self, aspect: f32) -> (f32, f32) {
+        let half_width = (self.fov.to_radians() / 2.).tan();
+        (half_width, half_width / aspect)
+    }
+
+    /// The origin and direction of the ray through `coord`,
+    /// from 0 at the top left to 1 at the bottom right, as in `create_ray` in `ray.hlsl`,
+    /// or `None` outside a fisheye's circle.
+    #[must_use]
+    pub fn ray(&self, coord: (f32, f32), aspect: f32) -> Option<(Vec3, Vec3)> {
+        let (forward, right, up) = self.get_vectors_fru();
+        let (eye, coord, aspect) = self.stereo.eye(coord, aspect);
+        // from -1 to 1 across the width, with the same scale up the height
+        let x = 2.0f32.mul_add(coord.0, -1.);
+        let y = (-2.0f32).mul_add(coord.1, 1.) / aspect;
+
+        // the eyes are apart along this axis
+        let (origin, direction, eye_axis) = match self.projection {
+            Projection::Perspective => {
+                let (half_width, _) = self.half_size(aspect);
+                let direction = (-forward + (right * x + up * y) * half_width).normalize();
+                (self.position, direction, right)
+            }
+            Projection::Orthographic { width } => {
+                let origin = self.position + (right * x + up * y) * width / 2.;
+                (origin, -forward, right)
+            }
+            Projection::Equirectangular => {
+                let longitude = x * PI;
+                let latitude = (-2.0f32).mul_add(coord.1, 1.) * FRAC_PI_2;
+                let direction = right * longitude.sin() * latitude.cos() + up * latitude.sin()
+                    - forward * longitude.cos() * latitude.cos();
+                // omni-directional stereo, the eyes turn with the direction looked in
+                let eye_axis = right * longitude.cos() + forward * longitude.sin();
+                (self.position, direction, eye_axis)
+            }
+            Projection::Fisheye => {
+                let radius = x.hypot(y);
+                // nothing is seen outside the circle, as in `ray.hlsl`
+                if radius > 1. {
+                    return None;
+                }
+                let angle = radius * self.fov.to_radians() / 2.;
+                let across = if radius > 0. {
+                    (right * x + up * y) / radius
+                } else {
+                    Vec3::zeros()
+                };
+                let direction = -forward * angle.cos() + across * angle.sin();
+                (self.position, direction, right)
+            }
+        };
+
+        Some((
+            origin + eye_axis * eye * self.stereo.eye_separation / 2.,
+            direction,
+        ))
+    }
+
+    /// Where `point` is on the image, from 0 at the top left to 1 at the bottom right,
+    /// or `None` if the camera can't see it.
+    ///
+    /// With stereo, this is where it is in the left eye's image.
+    #[must_use]
+    pub fn project(&self, point: Vec3, aspect: f32) -> Option<(f32, f32)> {
+        let (forward, right, up) = self.get_vectors_fru();
+        let aspect = self.stereo.eye_aspect(aspect);
+
+        let eye_position = if self.stereo.layout == StereoLayout::Off {
+            self.position
+        } else {
+            self.position - right * self.stereo.eye_separation / 2.
+        };
+        let offset = point - eye_position;
+        let local = Vec3::new(offset.dot(&right), offset.dot(&up), -offset.dot(&forward));
+
+        // from -1 to 1 across the width, with the same scale up the height
+        let (x, y) = match self.projection {
+            Projection::Perspective => {
+                if local.z <= 0. {
+                    return None;
+                }
+                let (half_width, _) = self.half_size(aspect);
+                (
+                    local.x / local.z / half_width,
+                    local.y / local.z / half_width,
+                )
+            }
+            Projection::Orthographic { width } => {
+                if local.z <= 0. {
+                    return None;
+                }
+                (local.x * 2. / width, local.y * 2. / width)
+            }
+            Projection::Equirectangular => {
+                let direction = local.try_normalize(f32::EPSILON)?;
+                let longitude = direction.x.atan2(direction.z);
+                let latitude = direction.y.asin();
+                (longitude / PI, latitude / FRAC_PI_2 / aspect)
+            }
+            Projection::Fisheye => {
+                let direction = local.try_normalize(f32::EPSILON)?;
+                let angle = direction.z.clamp(-1., 1.).acos();
+                let radius = angle / (self.fov.to_radians() / 2.);
+                if radius > 1. {
+                    return None;
+                }
+                let across = direction.x.hypot(direction.y);
+                if across <= 0. {
+                    (0., 0.)
+                } else {
+                    (direction.x / across * radius, direction.y / across * radius)
+                }
+            }
+        };
+
+        Some(
+            self.stereo
+                .left_eye_to_image((x.mul_add(0.5, 0.5), (y * aspect).mul_add(-0.5, 0.5))),
+        )
     }
 
     /// Calculates the forward, right, up vectors from the camera.
     ///
-    /// This is done together as each one depends on the one before it, so this saves calculations.
+    /// Forward points behind the camera, it looks along -forward.
     ///
     /// The 'fru' stands for forward, right, up as every time its used I need a reminder what order they are in.
     #[must_use]
     pub fn get_vectors_fru(&self) -> (Vec3, Vec3, Vec3) {
         puffin::profile_function!();
 
-        let forward = Rotation3::from_euler_angles(-self.rotation.x, -self.rotation.y, 0.)
-            * Vec3::new(0., 0., 1.);
-
-        let temp = Rotation3::from_euler_angles(0., 0., -self.rotation.z) * Vec3::new(0., 1., 0.);
-        let right = temp.cross(&forward).normalize();
-        let up = forward.cross(&right).normalize();
-
-        (forward, right, up)
+        (
+            self.orientation * Vec3::z(),
+            self.orientation * Vec3::x(),
+            self.orientation * Vec3::y(),
+        )
     }
 }
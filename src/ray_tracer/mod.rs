@@ -7,3 +7,15 @@ mod scene;
 pub use scene::*;
 mod camera;
 pub use camera::*;
+mod presets;
+pub use presets::*;
+mod sampler;
+pub use sampler::*;
+mod animation;
+pub use animation::*;
+mod physics;
+pub use physics::*;
+mod bookmarks;
+pub use bookmarks::*;
+mod physical_camera;
+pub use physical_camera::*;
@@ -1,9 +1,10 @@
-use super::Vec3;
+use super::{RigidBody, Vec3};
+use serde::{Deserialize, Serialize};
 
 use crate::bytes::{bytes_concat, AsBytes};
 
 /// These parameters influence how light interacts with the object.
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Material {
     /// The albedo colour.
     /// RGB from 0..1.
@@ -63,7 +64,8 @@ impl AsBytes<{ Self::BUFFER_SIZE }> for Material {
 /// Different types are:
 /// - Sphere
 /// - Plane
-#[derive(Clone, PartialEq)]
+/// - Quad
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum Geometry {
     /// A sphere.
     Sphere {
@@ -81,6 +83,17 @@ pub enum Geometry {
         /// The length of each side of the plane.
         size: f32,
     },
+    /// A rectangle bounded by two perpendicular edge vectors.
+    Quad {
+        /// The center of the quad.
+        center: Vec3,
+        /// Half of the first edge, from the center to the middle of a side.
+        u: Vec3,
+        /// Half of the second edge, from the center to the middle of a side.
+        ///
+        /// The quad faces towards `u x v`.
+        v: Vec3,
+    },
 }
 
 impl Geometry {
@@ -106,22 +119,81 @@ impl Geometry {
         }
     }
 
+    /// The default quad with centre 0, facing +Y, with sides of length 2
+    #[must_use]
+    pub const fn default_quad() -> Self {
+        Self::Quad {
+            center: Vec3::new(0., 0., 0.),
+            u: Vec3::new(0., 0., 1.),
+            v: Vec3::new(1., 0., 0.),
+        }
+    }
+
     /// Gets the position of the object to show in the editor.
     #[must_use]
     pub const fn position(&self) -> &Vec3 {
         match self {
-            Self::Plane { center, .. } | Self::Sphere { center, .. } => center,
+            Self::Plane { center, .. }
+            | Self::Sphere { center, .. }
+            | Self::Quad { center, .. } => center,
         }
     }
 
+    /// The distance along the ray to where it hits, as in `object_intersect` in `ray.hlsl`,
+    /// where `direction` is normalised.
+    #[must_use]
+    pub fn intersect(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let distance = match self {
+            Self::Sphere { center, radius } => {
+                let offset = origin - center;
+                let b = direction.dot(&offset);
+                let discriminant = b.mul_add(b, -offset.dot(&offset)) + radius * radius;
+                if discriminant < 0. {
+                    return None;
+                }
+                -b - discriminant.sqrt()
+            }
+            Self::Plane {
+                center,
+                normal,
+                size,
+            } => {
+                let distance = plane_distance(*center, *normal, origin, direction)?;
+                let offset = origin + direction * distance - center;
+                if offset.iter().any(|axis| axis.abs() > *size) {
+                    return None;
+                }
+                distance
+            }
+            Self::Quad { center, u, v } => {
+                let distance = plane_distance(*center, u.cross(v), origin, direction)?;
+                let offset = origin + direction * distance - center;
+                if offset.dot(u).abs() > u.dot(u) || offset.dot(v).abs() > v.dot(v) {
+                    return None;
+                }
+                distance
+            }
+        };
+
+        (distance > f32::EPSILON).then_some(distance)
+    }
+
     /// Gets the position of the object to show in the editor.
-    pub fn position_as_mut(&mut self) -> &mut Vec3 {
+    pub const fn position_as_mut(&mut self) -> &mut Vec3 {
         match self {
-            Self::Plane { center, .. } | Self::Sphere { center, .. } => center,
+            Self::Plane { center, .. }
+            | Self::Sphere { center, .. }
+            | Self::Quad { center, .. } => center,
         }
     }
 }
 
+/// The distance along the ray to the plane through `center` facing `normal`.
+fn plane_distance(center: Vec3, normal: Vec3, origin: Vec3, direction: Vec3) -> Option<f32> {
+    let denominator = direction.dot(&normal);
+    (denominator.abs() > f32::EPSILON).then(|| (center - origin).dot(&normal) / denominator)
+}
+
 impl AsBytes<{ Self::BUFFER_SIZE }> for Geometry {
     fn as_bytes(&self) -> [u8; Self::BUFFER_SIZE] {
         puffin::profile_function!();
@@ -152,12 +224,24 @@ impl AsBytes<{ Self::BUFFER_SIZE }> for Geometry {
                 ]
                 .into_iter(),
             ),
+            Self::Quad { center, u, v } => bytes_concat(
+                [
+                    &2u32.to_le_bytes(),
+                    [0u8; 12].as_slice(),
+                    &center.as_bytes(),
+                    &[0u8; 4],
+                    &u.as_bytes(),
+                    &[0u8; 4],
+                    &v.as_bytes(),
+                ]
+                .into_iter(),
+            ),
         }
     }
 }
 
 /// Stores all the information about an object.
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Object {
     /// The id of the object.
     /// Has to be unique.
@@ -170,11 +254,19 @@ pub struct Object {
     pub material: Material,
     /// The geometry of the object.
     pub geometry: Geometry,
+    /// How the object takes part in the physics simulation.
+    #[serde(default)]
+    pub body: RigidBody,
+    /// The units per second the object moves while the shutter is open.
+    ///
+    /// Set from the animation by [`Scene::update_motion`](super::Scene::update_motion).
+    #[serde(skip)]
+    pub velocity: Vec3,
 }
 
 impl Object {
     /// The size in bytes as represented in HLSL
-    pub const BUFFER_SIZE: usize = Material::BUFFER_SIZE + Geometry::BUFFER_SIZE;
+    pub const BUFFER_SIZE: usize = Material::BUFFER_SIZE + Geometry::BUFFER_SIZE + 16;
 
     /// The default sphere using [`Material::default`] and [`Geometry::default_sphere`]
     #[must_use]
@@ -188,6 +280,12 @@ impl Object {
         Self::new("plane", Material::default(), Geometry::default_plane())
     }
 
+    /// The default quad using [`Material::default`] and [`Geometry::default_quad`]
+    #[must_use]
+    pub fn default_quad() -> Self {
+        Self::new("quad", Material::default(), Geometry::default_quad())
+    }
+
     /// Creates a new object.
     pub fn new(name: impl Into<String>, material: Material, geometry: Geometry) -> Self {
         Self {
@@ -195,6 +293,8 @@ impl Object {
             name: name.into(),
             material,
             geometry,
+            body: RigidBody::default(),
+            velocity: Vec3::zeros(),
         }
     }
 }
@@ -207,6 +307,8 @@ impl AsBytes<{ Self::BUFFER_SIZE }> for Object {
             [
                 &self.material.as_bytes(),
                 self.geometry.as_bytes().as_slice(),
+                &self.velocity.as_bytes(),
+                &[0u8; 4],
             ]
             .into_iter(),
         )
@@ -218,7 +320,7 @@ impl AsBytes<{ Self::BUFFER_SIZE }> for Object {
 /// The different types are:
 /// - Direction
 /// - Point
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum Light {
     /// A direction light
     Direction {
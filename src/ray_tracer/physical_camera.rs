@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use super::Scene;
+
+/// A real camera's settings,
+/// which drive the fov, depth of field, motion blur shutter and exposure together.
+///
+/// Scenes are taken to be in metres, with light in physical units.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PhysicalCamera {
+    /// The width of the sensor in millimetres, 36 for full frame.
+    pub sensor_width: f32,
+    /// In millimetres.
+    pub focal_length: f32,
+    /// The focal length over the aperture's diameter.
+    pub f_number: f32,
+    /// The seconds the shutter is open for.
+    pub shutter_speed: f32,
+    /// The sensitivity of the sensor, higher is brighter.
+    pub iso: f32,
+    /// The distance in metres which is in focus.
+    pub focus_distance: f32,
+}
+
+impl Default for PhysicalCamera {
+    fn default() -> Self {
+        Self {
+            sensor_width: 36.,
+            focal_length: 50.,
+            f_number: 2.8,
+            shutter_speed: 1. / 60.,
+            iso: 100.,
+            focus_distance: 5.,
+        }
+    }
+}
+
+impl PhysicalCamera {
+    /// The horizontal fov in degrees.
+    #[must_use]
+    pub fn fov(&self) -> f32 {
+        (2. * (self.sensor_width / (2. * self.focal_length)).atan()).to_degrees()
+    }
+
+    /// The radius of the aperture in metres.
+    #[must_use]
+    pub fn aperture_radius(&self) -> f32 {
+        self.focal_length / self.f_number / 2. / 1000.
+    }
+
+    /// The exposure value at ISO 100.
+    #[must_use]
+    pub fn ev100(&self) -> f32 {
+        (self.f_number * self.f_number / self.shutter_speed * 100. / self.iso).log2()
+    }
+
+    /// What luminance in cd/m² is multiplied by to be displayed,
+    /// so the brightest luminance which doesn't saturate the sensor is shown as 1.
+    #[must_use]
+    pub fn exposure(&self) -> f32 {
+        // 1.2 is 78 / (100 * 0.65), the saturation based sensitivity over the lens attenuation
+        1. / (1.2 * self.ev100().exp2())
+    }
+}
+
+impl Scene {
+    /// Set the fov and motion blur shutter from the physical camera, if there is one.
+    pub fn apply_physical_camera(&mut self) {
+        if let Some(physical) = &self.physical_camera {
+            self.camera.fov = physical.fov();
+            self.motion_blur.shutter_open = 0.;
+            self.motion_blur.shutter_close = physical.shutter_speed;
+        }
+    }
+
+    /// The aperture radius and focus distance,
+    /// with a radius of 0 for a pinhole camera when there isn't a physical camera.
+    #[must_use]
+    pub fn lens(&self) -> (f32, f32) {
+        self.physical_camera.as_ref().map_or((0., 1.), |physical| {
+            (physical.aperture_radius(), physical.focus_distance)
+        })
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Geometry, Object, Scene, Vec3};
+
+/// How an object takes part in the physics simulation.
+///
+/// Only spheres move, planes and quads are always static.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RigidBody {
+    /// The mass in kilograms.
+    pub mass: f32,
+    /// Whether the object stays still, as if infinitely heavy.
+    pub is_static: bool,
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            mass: 1.,
+            is_static: false,
+        }
+    }
+}
+
+/// The constants of the simulation.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PhysicsSettings {
+    /// In metres per second squared.
+    pub gravity: Vec3,
+    /// The fraction of speed kept after bouncing, from 0 to 1.
+    pub restitution: f32,
+    /// How much objects sliding against each other slow down.
+    pub friction: f32,
+    /// Steps simulated per second, regardless of the frame rate.
+    pub steps_per_second: u32,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            gravity: Vec3::new(0., -9.81, 0.),
+            restitution: 0.6,
+            friction: 0.3,
+            steps_per_second: 120,
+        }
+    }
+}
+
+/// A sphere being simulated.
+struct Body {
+    index: usize,
+    center: Vec3,
+    radius: f32,
+    velocity: Vec3,
+    /// Zero for static bodies.
+    inverse_mass: f32,
+}
+
+/// A simple rigid body simulation of the spheres in a scene,
+/// colliding with each other and with planes and quads.
+pub struct Physics {
+    /// The user facing settings of the simulation.
+    pub settings: PhysicsSettings,
+    /// Whether the simulation runs each frame.
+    pub playing: bool,
+    step_requested: bool,
+    /// Time not simulated yet, as it's less than a step.
+    accumulator: f32,
+    velocities: HashMap<u128, Vec3>,
+    /// Where the spheres were before the simulation started.
+    initial_positions: Option<Vec<(u128, Vec3)>>,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Physics {
+    /// The most time simulated in one frame,
+    /// so a slow frame doesn't need even more steps to catch up.
+    const MAX_FRAME_TIME: f32 = 0.25;
+
+    /// A paused simulation with the default settings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            settings: PhysicsSettings::default(),
+            playing: false,
+            step_requested: false,
+            accumulator: 0.,
+            velocities: HashMap::new(),
+            initial_positions: None,
+        }
+    }
+
+    /// Simulate a single step on the next update.
+    pub const fn step_once(&mut self) {
+        self.step_requested = true;
+    }
+
+    /// Move the spheres back to where they were before the simulation started.
+    pub fn reset(&mut self, scene: &mut Scene) {
+        self.playing = false;
+        self.accumulator = 0.;
+        self.velocities.clear();
+
+        for (id, position) in self.initial_positions.take().unwrap_or_default() {
+            if let Some(object) = scene.objects.iter_mut().find(|object| object.id == id) {
+                *object.geometry.position_as_mut() = position;
+            }
+        }
+    }
+
+    /// Simulate as many fixed steps as fit in the time since the last update.
+    pub fn update(&mut self, scene: &mut Scene, delta_time: f32) {
+        puffin::profile_function!();
+
+        let timestep = 1. / self.settings.steps_per_second.max(1) as f32;
+        let mut steps = 0;
+
+        if self.playing {
+            self.accumulator += delta_time.min(Self::MAX_FRAME_TIME);
+            steps = (self.accumulator / timestep) as u32;
+            self.accumulator -= steps as f32 * timestep;
+        }
+        if self.step_requested {
+            self.step_requested = false;
+            steps += 1;
+        }
+
+        if steps == 0 {
+            return;
+        }
+
+        if self.initial_positions.is_none() {
+            self.initial_positions = Some(
+                scene
+                    .objects
+                    .iter()
+                    .map(|object| (object.id, *object.geometry.position()))
+                    .collect(),
+            );
+        }
+
+        let mut bodies = self.bodies(&scene.objects);
+        for _ in 0..steps {
+            self.step(&mut bodies, &scene.objects, timestep);
+        }
+
+        for body in bodies {
+            let object = &mut scene.objects[body.index];
+            *object.geometry.position_as_mut() = body.center;
+            self.velocities.insert(object.id, body.velocity);
+        }
+    }
+
+    fn bodies(&self, objects: &[Object]) -> Vec<Body> {
+        objects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, object)| {
+                let Geometry::Sphere { center, radius } = object.geometry else {
+                    return None;
+                };
+
+                Some(Body {
+                    index,
+                    center,
+                    radius,
+                    velocity: self.velocities.get(&object.id).copied().unwrap_or_default(),
+                    inverse_mass: if object.body.is_static {
+                        0.
+                    } else {
+                        1. / object.body.mass.max(0.001)
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn step(&self, bodies: &mut [Body], objects: &[Object], timestep: f32) {
+        for body in bodies.iter_mut().filter(|body| body.inverse_mass > 0.) {
+            body.velocity += self.settings.gravity * timestep;
+            body.center += body.velocity * timestep;
+        }
+
+        for i in 0..bodies.len() {
+            let (before, after) = bodies.split_at_mut(i + 1);
+            let a = &mut before[i];
+
+            for b in after {
+                let offset = b.center - a.center;
+                let distance = offset.magnitude();
+                let penetration = a.radius + b.radius - distance;
+
+                if penetration > 0. && distance > f32::EPSILON {
+                    self.collide(a, Some(b), offset / distance, penetration);
+                }
+            }
+
+            for object in objects {
+                if let Some((normal, penetration)) = surface_contact(&object.geometry, a) {
+                    self.collide(a, None, -normal, penetration);
+                }
+            }
+        }
+    }
+
+    /// Push two overlapping bodies apart and bounce them off each other,
+    /// where `normal` points from `a` to `b`, and a missing `b` is static.
+    fn collide(&self, a: &mut Body, mut b: Option<&mut Body>, normal: Vec3, penetration: f32) {
+        let b_inverse_mass = b.as_ref().map_or(0., |b| b.inverse_mass);
+        let total_inverse_mass = a.inverse_mass + b_inverse_mass;
+        if total_inverse_mass <= 0. {
+            return;
+        }
+
+        a.center -= normal * penetration * a.inverse_mass / total_inverse_mass;
+        if let Some(b) = &mut b {
+            b.center += normal * penetration * b.inverse_mass / total_inverse_mass;
+        }
+
+        let relative = b.as_ref().map_or_else(Vec3::zeros, |b| b.velocity) - a.velocity;
+        let normal_speed = relative.dot(&normal);
+        // already separating
+        if normal_speed >= 0. {
+            return;
+        }
+
+        let impulse = -(1. + self.settings.restitution) * normal_speed / total_inverse_mass;
+        a.velocity -= normal * impulse * a.inverse_mass;
+        if let Some(b) = &mut b {
+            b.velocity += normal * impulse * b.inverse_mass;
+        }
+
+        // friction opposes sliding, but can't reverse it
+        let tangent = relative - normal * normal_speed;
+        let tangent_speed = tangent.magnitude();
+        if tangent_speed > f32::EPSILON {
+            let friction_impulse =
+                (self.settings.friction * impulse).min(tangent_speed / total_inverse_mass);
+            let direction = tangent / tangent_speed;
+
+            a.velocity += direction * friction_impulse * a.inverse_mass;
+            if let Some(b) = &mut b {
+                b.velocity -= direction * friction_impulse * b.inverse_mass;
+            }
+        }
+    }
+}
+
+/// The normal and depth of a body overlapping a plane or quad.
+fn surface_contact(geometry: &Geometry, body: &Body) -> Option<(Vec3, f32)> {
+    let (center, normal, within_bounds): (_, _, &dyn Fn(Vec3) -> bool) = match geometry {
+        Geometry::Sphere { .. } => return None,
+        Geometry::Plane {
+            center,
+            normal,
+            size,
+        } => (*center, normal.normalize(), &|offset: Vec3| {
+            offset.iter().all(|axis| axis.abs() <= *size)
+        }),
+        Geometry::Quad { center, u, v } => (*center, u.cross(v).normalize(), &|offset: Vec3| {
+            offset.dot(u).abs() <= u.dot(u) && offset.dot(v).abs() <= v.dot(v)
+        }),
+    };
+
+    let offset = body.center - center;
+    let height = offset.dot(&normal);
+    // collide with whichever side the body is on
+    let (normal, height) = if height < 0. {
+        (-normal, -height)
+    } else {
+        (normal, height)
+    };
+
+    let penetration = body.radius - height;
+    (penetration > 0. && within_bounds(offset - normal * height)).then_some((normal, penetration))
+}
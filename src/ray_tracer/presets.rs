@@ -0,0 +1,398 @@
+use nalgebra::{Rotation3, UnitQuaternion};
+
+use super::{
+    Animation, Camera, FireflySettings, Geometry, Material, MotionBlurSettings, Object, Projection,
+    Sampler, Scene, Stereo, Vec3,
+};
+
+/// The built in scenes, used as references when judging the renderer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScenePreset {
+    /// [`Scene::simple`]
+    Simple,
+    /// [`Scene::random_spheres_default_config`]
+    RandomSpheres,
+    /// [`Scene::cornell_box`]
+    CornellBox,
+    /// [`Scene::material_grid`]
+    MaterialGrid,
+    /// [`Scene::furnace`]
+    Furnace,
+    /// [`Scene::emission_test`]
+    Emission,
+}
+
+impl ScenePreset {
+    /// Every preset, in the order they are shown in the menu.
+    pub const ALL: [Self; 6] = [
+        Self::Simple,
+        Self::RandomSpheres,
+        Self::CornellBox,
+        Self::MaterialGrid,
+        Self::Furnace,
+        Self::Emission,
+    ];
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Simple => "Simple",
+            Self::RandomSpheres => "Random spheres",
+            Self::CornellBox => "Cornell box",
+            Self::MaterialGrid => "Material grid",
+            Self::Furnace => "Furnace test",
+            Self::Emission => "Emission test",
+        }
+    }
+
+    /// The name used on the command line.
+    #[must_use]
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Simple => "simple",
+            Self::RandomSpheres => "random-spheres",
+            Self::CornellBox => "cornell-box",
+            Self::MaterialGrid => "material-grid",
+            Self::Furnace => "furnace",
+            Self::Emission => "emission",
+        }
+    }
+
+    /// Find the preset with the given command line name.
+    #[must_use]
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.id() == id)
+    }
+
+    /// Build the scene.
+    #[must_use]
+    pub fn scene(self) -> Scene {
+        match self {
+            Self::Simple => Scene::simple(),
+            Self::RandomSpheres => Scene::random_spheres_default_config(),
+            Self::CornellBox => Scene::cornell_box(),
+            Self::MaterialGrid => Scene::material_grid(),
+            Self::Furnace => Scene::furnace(),
+            Self::Emission => Scene::emission_test(),
+        }
+    }
+}
+
+/// A purely diffuse material.
+const fn diffuse(colour: Vec3) -> Material {
+    Material {
+        colour,
+        emission: Vec3::new(0., 0., 0.),
+        emission_strength: 0.,
+        metallic: 0.,
+        roughness: 1.,
+    }
+}
+
+/// Creates a box out of quads, rotated around the Y axis.
+///
+/// The bottom face is left out, as it always sits on the floor.
+fn cuboid(
+    name: &str,
+    material: &Material,
+    center: Vec3,
+    half_size: Vec3,
+    angle: f32,
+) -> Vec<Object> {
+    let rotation = Rotation3::from_euler_angles(0., angle, 0.);
+    let x = rotation * Vec3::new(half_size.x, 0., 0.);
+    let y = Vec3::new(0., half_size.y, 0.);
+    let z = rotation * Vec3::new(0., 0., half_size.z);
+
+    [
+        ("right", center + x, y, z),
+        ("left", center - x, z, y),
+        ("top", center + y, z, x),
+        ("front", center + z, x, y),
+        ("back", center - z, y, x),
+    ]
+    .into_iter()
+    .map(|(side, center, u, v)| {
+        Object::new(
+            format!("{name} {side}"),
+            material.clone(),
+            Geometry::Quad { center, u, v },
+        )
+    })
+    .collect()
+}
+
+impl Scene {
+    /// The Cornell box, lit by a single quad light in the ceiling.
+    ///
+    /// The box is 2 units wide, open towards the camera.
+    #[must_use]
+    pub fn cornell_box() -> Self {
+        let white = diffuse(Vec3::new(0.73, 0.73, 0.73));
+        let red = diffuse(Vec3::new(0.65, 0.05, 0.05));
+        let green = diffuse(Vec3::new(0.12, 0.45, 0.15));
+
+        let wall = |name: &str, material: &Material, center: Vec3, u: Vec3, v: Vec3| {
+            Object::new(name, material.clone(), Geometry::Quad { center, u, v })
+        };
+
+        let mut objects = vec![
+            wall(
+                "Floor",
+                &white,
+                Vec3::new(0., 0., 0.),
+                Vec3::new(0., 0., 1.),
+                Vec3::new(1., 0., 0.),
+            ),
+            wall(
+                "Ceiling",
+                &white,
+                Vec3::new(0., 2., 0.),
+                Vec3::new(1., 0., 0.),
+                Vec3::new(0., 0., 1.),
+            ),
+            wall(
+                "Back wall",
+                &white,
+                Vec3::new(0., 1., -1.),
+                Vec3::new(1., 0., 0.),
+                Vec3::new(0., 1., 0.),
+            ),
+            wall(
+                "Left wall",
+                &red,
+                Vec3::new(-1., 1., 0.),
+                Vec3::new(0., 1., 0.),
+                Vec3::new(0., 0., 1.),
+            ),
+            wall(
+                "Right wall",
+                &green,
+                Vec3::new(1., 1., 0.),
+                Vec3::new(0., 0., 1.),
+                Vec3::new(0., 1., 0.),
+            ),
+            wall(
+                "Light",
+                &Material {
+                    emission: Vec3::new(1., 0.85, 0.6),
+                    emission_strength: 15.,
+                    ..white.clone()
+                },
+                Vec3::new(0., 1.999, 0.),
+                Vec3::new(0.25, 0., 0.),
+                Vec3::new(0., 0., 0.25),
+            ),
+        ];
+
+        objects.extend(cuboid(
+            "Tall box",
+            &white,
+            Vec3::new(-0.35, 0.6, -0.3),
+            Vec3::new(0.3, 0.6, 0.3),
+            0.3,
+        ));
+        objects.extend(cuboid(
+            "Short box",
+            &white,
+            Vec3::new(0.35, 0.3, 0.3),
+            Vec3::new(0.3, 0.3, 0.3),
+            -0.3,
+        ));
+
+        Self {
+            camera: Camera {
+                position: Vec3::new(0., 1., 3.9),
+                orientation: UnitQuaternion::identity(),
+                fov: 40.,
+                projection: Projection::Perspective,
+                stereo: Stereo::default(),
+            },
+            objects,
+            lights: vec![],
+            background_colour: Vec3::new(0., 0., 0.),
+            use_hdri: false,
+            ambient_light: Vec3::new(0., 0., 0.),
+            reflection_limit: 8,
+            seed: 0,
+            sampler: Sampler::Independent,
+            russian_roulette: true,
+            roulette_depth: 3,
+            fireflies: FireflySettings::default(),
+            animation: Animation::default(),
+            motion_blur: MotionBlurSettings::default(),
+            physical_camera: None,
+            bookmarks: Vec::new(),
+            camera_shutter: None,
+        }
+    }
+
+    /// A 5x5 grid of spheres,
+    /// with roughness increasing to the right and metallic increasing towards the camera.
+    #[must_use]
+    pub fn material_grid() -> Self {
+        let mut objects = Vec::new();
+
+        for x in 0..5u8 {
+            for z in 0..5u8 {
+                let roughness = f32::from(x) / 4.;
+                let metallic = f32::from(z) / 4.;
+
+                objects.push(Object::new(
+                    format!("Roughness {roughness:.2} metallic {metallic:.2}"),
+                    Material {
+                        colour: Vec3::new(0.9, 0.6, 0.2),
+                        emission: Vec3::new(0., 0., 0.),
+                        emission_strength: 0.,
+                        metallic,
+                        roughness,
+                    },
+                    Geometry::Sphere {
+                        center: Vec3::new(f32::from(x) - 2., 0.4, f32::from(z) - 2.),
+                        radius: 0.4,
+                    },
+                ));
+            }
+        }
+
+        objects.push(Object::new(
+            "Plane",
+            diffuse(Vec3::new(0.5, 0.5, 0.5)),
+            Geometry::Plane {
+                center: Vec3::new(0., 0., 0.),
+                normal: Vec3::new(0., 1., 0.),
+                size: 100.,
+            },
+        ));
+
+        Self {
+            camera: Camera {
+                position: Vec3::new(0., 4., 6.),
+                orientation: Camera::orientation_from_euler(Vec3::new(0.59, 0., 0.)),
+                fov: 50.,
+                projection: Projection::Perspective,
+                stereo: Stereo::default(),
+            },
+            objects,
+            lights: vec![],
+            background_colour: Vec3::new(0.5, 0.8, 1.),
+            use_hdri: true,
+            ambient_light: Vec3::new(0., 0., 0.),
+            reflection_limit: 6,
+            seed: 0,
+            sampler: Sampler::Independent,
+            russian_roulette: true,
+            roulette_depth: 3,
+            fireflies: FireflySettings::default(),
+            animation: Animation::default(),
+            motion_blur: MotionBlurSettings::default(),
+            physical_camera: None,
+            bookmarks: Vec::new(),
+            camera_shutter: None,
+        }
+    }
+
+    /// A white sphere inside a uniform grey environment.
+    ///
+    /// If energy is conserved, the sphere should be almost invisible against the background.
+    #[must_use]
+    pub fn furnace() -> Self {
+        Self {
+            camera: Camera {
+                position: Vec3::new(0., 0., 4.),
+                orientation: UnitQuaternion::identity(),
+                fov: 50.,
+                projection: Projection::Perspective,
+                stereo: Stereo::default(),
+            },
+            objects: vec![Object::new(
+                "Sphere",
+                diffuse(Vec3::new(1., 1., 1.)),
+                Geometry::default_sphere(),
+            )],
+            lights: vec![],
+            background_colour: Vec3::new(0.5, 0.5, 0.5),
+            use_hdri: false,
+            ambient_light: Vec3::new(0., 0., 0.),
+            reflection_limit: 20,
+            seed: 0,
+            sampler: Sampler::Independent,
+            russian_roulette: true,
+            roulette_depth: 3,
+            fireflies: FireflySettings::default(),
+            animation: Animation::default(),
+            motion_blur: MotionBlurSettings::default(),
+            physical_camera: None,
+            bookmarks: Vec::new(),
+            camera_shutter: None,
+        }
+    }
+
+    /// A row of emissive spheres with increasing strength, over a grey floor in the dark.
+    #[must_use]
+    pub fn emission_test() -> Self {
+        let emissions = [
+            (0.5, Vec3::new(1., 1., 1.)),
+            (1., Vec3::new(1., 0., 0.)),
+            (2., Vec3::new(0., 1., 0.)),
+            (5., Vec3::new(0., 0., 1.)),
+            (10., Vec3::new(1., 1., 0.)),
+        ];
+
+        let mut objects = emissions
+            .into_iter()
+            .enumerate()
+            .map(|(i, (emission_strength, emission))| {
+                Object::new(
+                    format!("Strength {emission_strength}"),
+                    Material {
+                        emission,
+                        emission_strength,
+                        ..diffuse(Vec3::new(0.8, 0.8, 0.8))
+                    },
+                    Geometry::Sphere {
+                        center: Vec3::new(i as f32 - 2., 0.4, 0.),
+                        radius: 0.4,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        objects.push(Object::new(
+            "Plane",
+            diffuse(Vec3::new(0.5, 0.5, 0.5)),
+            Geometry::Plane {
+                center: Vec3::new(0., 0., 0.),
+                normal: Vec3::new(0., 1., 0.),
+                size: 100.,
+            },
+        ));
+
+        Self {
+            camera: Camera {
+                position: Vec3::new(0., 1.5, 4.5),
+                orientation: Camera::orientation_from_euler(Vec3::new(0.24, 0., 0.)),
+                fov: 50.,
+                projection: Projection::Perspective,
+                stereo: Stereo::default(),
+            },
+            objects,
+            lights: vec![],
+            background_colour: Vec3::new(0., 0., 0.),
+            use_hdri: false,
+            ambient_light: Vec3::new(0., 0., 0.),
+            reflection_limit: 4,
+            seed: 0,
+            sampler: Sampler::Independent,
+            russian_roulette: true,
+            roulette_depth: 3,
+            fireflies: FireflySettings::default(),
+            animation: Animation::default(),
+            motion_blur: MotionBlurSettings::default(),
+            physical_camera: None,
+            bookmarks: Vec::new(),
+            camera_shutter: None,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+/// How the sample points of each pixel are generated, as numbered in `sampler.hlsl`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Sampler {
+    /// Uncorrelated random numbers.
+    Independent,
+    /// Random points within a shuffled 16x16 grid of strata.
+    Stratified,
+    /// The Sobol sequence with hash based Owen scrambling.
+    Sobol,
+    /// The R2 sequence, dithered between pixels with a blue noise like mask.
+    R2,
+}
+
+impl Sampler {
+    /// Every sampler, in the order they are numbered in `sampler.hlsl`.
+    pub const ALL: [Self; 4] = [Self::Independent, Self::Stratified, Self::Sobol, Self::R2];
+
+    /// The name shown in the UI.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Independent => "Independent",
+            Self::Stratified => "Stratified",
+            Self::Sobol => "Owen scrambled Sobol",
+            Self::R2 => "Blue noise R2",
+        }
+    }
+
+    /// The number passed to the shader.
+    #[must_use]
+    pub const fn index(self) -> u32 {
+        self as u32
+    }
+}
@@ -1,10 +1,19 @@
-use super::{Camera, Geometry, Light, Material, Object, Vec3};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use nalgebra::UnitQuaternion;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Animation, Bookmark, Camera, Geometry, Light, Material, Object, PhysicalCamera, Projection,
+    Sampler, Stereo, Vec3,
+};
 use crate::bytes::{bytes_concat, bytes_concat_owned, AsBytes as _};
 use rand::{Rng, SeedableRng};
 use rand_distr::Distribution;
 
 /// Stores all the information about a scene
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Scene {
     /// The camera
     pub camera: Camera,
@@ -13,13 +22,127 @@ pub struct Scene {
     /// The lights
     pub lights: Vec<Light>,
     /// The background colour
+    ///
+    /// This is only used when `use_hdri` is false.
     pub background_colour: Vec3,
+    /// Whether the HDRI is used as the environment,
+    /// instead of a uniform `background_colour`.
+    pub use_hdri: bool,
     /// The ambient light
     pub ambient_light: Vec3,
     /// The bounce limit
+    ///
+    /// With russian roulette this is only a safety cap,
+    /// as paths are ended randomly once they have lost most of their energy.
     pub reflection_limit: u32,
-    /// Whether objects should spin
-    pub do_objects_spin: bool,
+    /// Seeds the random numbers used while rendering,
+    /// so renders with the same seed are identical.
+    pub seed: u32,
+    /// How the sample points of each pixel are generated.
+    pub sampler: Sampler,
+    /// Whether paths are randomly ended based on their remaining energy,
+    /// with the energy of surviving paths boosted to keep the render unbiased.
+    pub russian_roulette: bool,
+    /// The bounces before russian roulette starts.
+    pub roulette_depth: u32,
+    /// Biased ways of suppressing fireflies.
+    pub fireflies: FireflySettings,
+    /// Blurring moving things over the time the shutter is open.
+    #[serde(default)]
+    pub motion_blur: MotionBlurSettings,
+    /// Drives the fov, depth of field, shutter and exposure from a real camera's settings.
+    #[serde(default)]
+    pub physical_camera: Option<PhysicalCamera>,
+    /// Saved camera views.
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// The camera as the shutter opens and closes, if it moves while it is open.
+    ///
+    /// Set from the animation by [`Scene::update_motion`].
+    #[serde(skip)]
+    pub camera_shutter: Option<[Camera; 2]>,
+    /// The keyframed tracks which move things in the scene.
+    #[serde(default)]
+    pub animation: Animation,
+}
+
+/// The part of the image being rendered.
+///
+/// Large images are rendered in tiles, each of which is a viewport of the full image.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Viewport {
+    /// The top left pixel of the viewport within the full image.
+    pub offset: (u32, u32),
+    /// The size of the viewport, which is the size of the render target.
+    pub size: (u32, u32),
+    /// The size of the full image.
+    pub full_size: (u32, u32),
+}
+
+impl Viewport {
+    /// The viewport covering the whole of an image.
+    #[must_use]
+    pub const fn full(size: (u32, u32)) -> Self {
+        Self {
+            offset: (0, 0),
+            size,
+            full_size: size,
+        }
+    }
+}
+
+/// Ways of suppressing fireflies, the rare very bright samples from small bright lights.
+///
+/// These all bias the render, trading energy for faster convergence.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FireflySettings {
+    /// Whether the light each sample gathers is clamped to
+    /// `clamp_direct` and `clamp_indirect`.
+    ///
+    /// This loses energy from bright highlights.
+    pub clamp_radiance: bool,
+    /// The brightest light gathered on the first bounce.
+    pub clamp_direct: f32,
+    /// The brightest light gathered on later bounces.
+    pub clamp_indirect: f32,
+    /// Whether surfaces hit after a rough bounce are treated as at least as rough,
+    /// to avoid sharp reflections of small lights seen through diffuse surfaces.
+    ///
+    /// This blurs caustics and glossy inter-reflections.
+    pub path_roughening: bool,
+}
+
+/// The time the shutter is open for, to blur things which move.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MotionBlurSettings {
+    /// Whether the camera and objects move while the shutter is open.
+    pub enabled: bool,
+    /// Seconds after the animation time that the shutter opens.
+    pub shutter_open: f32,
+    /// Seconds after the animation time that the shutter closes.
+    pub shutter_close: f32,
+}
+
+impl Default for MotionBlurSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shutter_open: 0.,
+            // a 180 degree shutter at 24 fps
+            shutter_close: 1. / 48.,
+        }
+    }
+}
+
+impl Default for FireflySettings {
+    fn default() -> Self {
+        Self {
+            clamp_radiance: false,
+            clamp_direct: 10.,
+            clamp_indirect: 3.,
+            path_roughening: false,
+        }
+    }
 }
 
 impl Scene {
@@ -30,7 +153,7 @@ impl Scene {
     /// The max number of lights
     pub const MAX_LIGHTS: usize = 2;
     /// The size in bytes as represented in HLSL
-    pub const CONFIG_SIZE: usize = 112;
+    pub const CONFIG_SIZE: usize = 272;
 
     /// The size in bytes as represented in HLSL
     /// of (objects, lights, config)
@@ -64,13 +187,25 @@ impl Scene {
             }],
             camera: Camera {
                 position: Vec3::new(0., 0., -5.),
-                rotation: Vec3::new(0., 0., 0.),
+                orientation: UnitQuaternion::identity(),
                 fov: 70.,
+                projection: Projection::Perspective,
+                stereo: Stereo::default(),
             },
             background_colour: Vec3::new(0.5, 0.8, 1.),
+            use_hdri: true,
             ambient_light: Vec3::new(0.2, 0.2, 0.2),
             reflection_limit: 4,
-            do_objects_spin: false,
+            seed: 0,
+            sampler: Sampler::Independent,
+            russian_roulette: true,
+            roulette_depth: 3,
+            fireflies: FireflySettings::default(),
+            animation: Animation::default(),
+            motion_blur: MotionBlurSettings::default(),
+            physical_camera: None,
+            bookmarks: Vec::new(),
+            camera_shutter: None,
         }
     }
 
@@ -209,8 +344,10 @@ impl Scene {
         Self {
             camera: Camera {
                 position: Vec3::new(55., 65., 55.),
-                rotation: Vec3::new(0.8, -0.8, 0.),
+                orientation: Camera::orientation_from_euler(Vec3::new(0.8, -0.8, 0.)),
                 fov: 70.,
+                projection: Projection::Perspective,
+                stereo: Stereo::default(),
             },
             objects,
             lights: vec![
@@ -224,19 +361,76 @@ impl Scene {
                 },
             ],
             background_colour: Vec3::new(0.5, 0.8, 1.),
+            use_hdri: true,
             ambient_light: Vec3::new(0.2, 0.2, 0.2),
             reflection_limit: 3,
-            do_objects_spin: false,
+            seed: 0,
+            sampler: Sampler::Independent,
+            russian_roulette: true,
+            roulette_depth: 3,
+            fireflies: FireflySettings::default(),
+            animation: Animation::default(),
+            motion_blur: MotionBlurSettings::default(),
+            physical_camera: None,
+            bookmarks: Vec::new(),
+            camera_shutter: None,
         }
     }
 
+    /// Save the scene, including its animation, as JSON.
+    ///
+    /// # Errors
+    ///
+    /// If the file couldn't be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        puffin::profile_function!();
+
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Load a scene saved with [`Scene::save`].
+    ///
+    /// # Errors
+    ///
+    /// If the file couldn't be read or isn't a valid scene.
+    pub fn load(path: &Path) -> Result<Self> {
+        puffin::profile_function!();
+
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut scene: Self = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid scene {}", path.display()))?;
+        // edited files may not keep the keyframes in order
+        scene.animation.sort_keys();
+
+        Ok(scene)
+    }
+
+    /// The index of the closest object seen at `coord` on the image and where it was hit,
+    /// where `aspect` is the image's width over its height.
+    #[must_use]
+    pub fn pick(&self, coord: (f32, f32), aspect: f32) -> Option<(usize, Vec3)> {
+        puffin::profile_function!();
+
+        let (origin, direction) = self.camera.ray(coord, aspect)?;
+
+        self.objects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, object)| {
+                Some((index, object.geometry.intersect(origin, direction)?))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, distance)| (index, origin + direction * distance))
+    }
+
     /// Get the struct represented as bytes, packed with HLSL's rules.
     /// Can't implement `AsBytes` because this maps to 3 separate buffers.
     #[must_use]
     pub fn as_bytes(
         &self,
-        width: u32,
-        height: u32,
+        viewport: &Viewport,
     ) -> (
         [u8; Self::BUFFER_SIZE.0],
         [u8; Self::BUFFER_SIZE.1],
@@ -244,14 +438,28 @@ impl Scene {
     ) {
         puffin::profile_function!();
 
-        let vectors = self.camera.get_vectors_fru();
+        let (open, close) = self
+            .camera_shutter
+            .as_ref()
+            .map_or((&self.camera, &self.camera), |[open, close]| (open, close));
+        let vectors = open.get_vectors_fru();
+        let (lens_radius, focus_distance) = self.lens();
+        let vectors_end = close.get_vectors_fru();
+        let (shutter_open, shutter_close) = if self.motion_blur.enabled {
+            (
+                self.motion_blur.shutter_open,
+                self.motion_blur.shutter_close,
+            )
+        } else {
+            (0., 0.)
+        };
 
         (
             bytes_concat_owned(self.objects.iter().map(Object::as_bytes)),
             bytes_concat_owned(self.lights.iter().map(Light::as_bytes)),
             bytes_concat(
                 [
-                    &self.camera.position.as_bytes(),
+                    &open.position.as_bytes(),
                     [0u8; 4].as_slice(),
                     &vectors.0.as_bytes(),
                     &[0u8; 4],
@@ -264,8 +472,40 @@ impl Scene {
                     &self.ambient_light.as_bytes(),
                     &self.camera.fov.to_le_bytes(),
                     &self.reflection_limit.to_le_bytes(),
-                    &width.to_le_bytes(),
-                    &height.to_le_bytes(),
+                    &viewport.size.0.to_le_bytes(),
+                    &viewport.size.1.to_le_bytes(),
+                    &u32::from(self.use_hdri).to_le_bytes(),
+                    &self.seed.to_le_bytes(),
+                    &self.sampler.index().to_le_bytes(),
+                    &u32::from(self.russian_roulette).to_le_bytes(),
+                    &self.roulette_depth.to_le_bytes(),
+                    &u32::from(self.fireflies.clamp_radiance).to_le_bytes(),
+                    &self.fireflies.clamp_direct.to_le_bytes(),
+                    &self.fireflies.clamp_indirect.to_le_bytes(),
+                    &u32::from(self.fireflies.path_roughening).to_le_bytes(),
+                    &viewport.offset.0.to_le_bytes(),
+                    &viewport.offset.1.to_le_bytes(),
+                    &viewport.full_size.0.to_le_bytes(),
+                    &viewport.full_size.1.to_le_bytes(),
+                    &shutter_open.to_le_bytes(),
+                    &shutter_close.to_le_bytes(),
+                    &[0u8; 8],
+                    &close.position.as_bytes(),
+                    &[0u8; 4],
+                    &vectors_end.0.as_bytes(),
+                    &[0u8; 4],
+                    &vectors_end.1.as_bytes(),
+                    &[0u8; 4],
+                    &vectors_end.2.as_bytes(),
+                    &[0u8; 4],
+                    &self.camera.projection.index().to_le_bytes(),
+                    &self.camera.projection.orthographic_width().to_le_bytes(),
+                    &self.camera.stereo.layout.index().to_le_bytes(),
+                    &self.camera.stereo.eye_separation.to_le_bytes(),
+                    &lens_radius.to_le_bytes(),
+                    &focus_distance.to_le_bytes(),
+                    // pads to a multiple of 16 bytes
+                    &[0u8; 8],
                 ]
                 .into_iter(),
             ),
@@ -305,8 +545,86 @@ impl PartialEq for Scene {
                 }
             }
             && (self.background_colour == other.background_colour)
+            && (self.use_hdri == other.use_hdri)
             && (self.ambient_light == other.ambient_light)
             && (self.reflection_limit == other.reflection_limit)
-            && (self.do_objects_spin == other.do_objects_spin)
+            && (self.seed == other.seed)
+            && (self.sampler == other.sampler)
+            && (self.russian_roulette == other.russian_roulette)
+            && (self.roulette_depth == other.roulette_depth)
+            && (self.fireflies == other.fireflies)
+            && (self.motion_blur == other.motion_blur)
+            && (self.physical_camera == other.physical_camera)
+            && (self.camera_shutter == other.camera_shutter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray_tracer::StereoLayout;
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    }
+
+    fn read_f32(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_bits(read_u32(bytes, offset))
+    }
+
+    fn read_vec3(bytes: &[u8], offset: usize) -> Vec3 {
+        Vec3::new(
+            read_f32(bytes, offset),
+            read_f32(bytes, offset + 4),
+            read_f32(bytes, offset + 8),
+        )
+    }
+
+    /// The offsets are of `Config` in inputs.hlsl, laid out by the uniform buffer rules.
+    #[test]
+    fn config_matches_shader_layout() {
+        let mut scene = Scene::simple();
+        scene.camera.projection = Projection::Orthographic { width: 7.5 };
+        scene.camera.stereo = Stereo {
+            layout: StereoLayout::SideBySide,
+            eye_separation: 0.25,
+        };
+        scene.physical_camera = Some(PhysicalCamera {
+            focal_length: 50.,
+            f_number: 5.,
+            focus_distance: 3.,
+            ..PhysicalCamera::default()
+        });
+        let viewport = Viewport {
+            offset: (3, 5),
+            size: (7, 11),
+            full_size: (13, 17),
+        };
+        let (_, _, config) = scene.as_bytes(&viewport);
+
+        // tile_offset
+        assert_eq!(read_u32(&config, 144), 3);
+        assert_eq!(read_u32(&config, 148), 5);
+        // full_size
+        assert_eq!(read_u32(&config, 152), 13);
+        assert_eq!(read_u32(&config, 156), 17);
+        // position_end, without motion blur the camera doesn't move
+        assert_eq!(read_vec3(&config, 176), scene.camera.position);
+        // up_end
+        assert_eq!(read_vec3(&config, 224), scene.camera.get_vectors_fru().2);
+        // projection and ortho_width
+        assert_eq!(read_u32(&config, 240), 1);
+        assert_eq!(read_u32(&config, 244), 7.5f32.to_bits());
+        // stereo and eye_separation
+        assert_eq!(read_u32(&config, 248), 1);
+        assert_eq!(read_u32(&config, 252), 0.25f32.to_bits());
+        // lens_radius and focus_distance
+        assert_eq!(read_u32(&config, 256), 0.005f32.to_bits());
+        assert_eq!(read_u32(&config, 260), 3f32.to_bits());
     }
 }
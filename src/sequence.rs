@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+/// The file format frames are saved in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrameFormat {
+    /// The linear beauty render.
+    Exr,
+    /// The displayed image, as shown in the viewport.
+    Png,
+}
+
+impl FrameFormat {
+    /// Every format, in the order they are shown in the UI.
+    pub const ALL: [Self; 2] = [Self::Exr, Self::Png];
+
+    /// The file extension, also used to choose the format on the command line.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Exr => "exr",
+            Self::Png => "png",
+        }
+    }
+
+    /// Get the format with this extension.
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == extension)
+    }
+}
+
+/// Settings for rendering the animation to numbered images.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SequenceSettings {
+    /// The folder the frames are saved in.
+    pub directory: String,
+    /// The image format each frame is saved as.
+    pub format: FrameFormat,
+    /// Frames per second of animation time.
+    pub frame_rate: f32,
+    /// The first frame rendered, where frame 1 is at time 0.
+    pub first_frame: u32,
+    /// The last frame rendered, inclusive.
+    pub last_frame: u32,
+    /// The passes over the image each frame is rendered with.
+    pub passes: u32,
+}
+
+impl Default for SequenceSettings {
+    fn default() -> Self {
+        Self {
+            directory: "./frames".to_string(),
+            format: FrameFormat::Exr,
+            frame_rate: 24.,
+            first_frame: 1,
+            last_frame: 48,
+            passes: 64,
+        }
+    }
+}
+
+impl SequenceSettings {
+    /// Where a frame is saved.
+    #[must_use]
+    pub fn path(&self, frame: u32) -> PathBuf {
+        PathBuf::from(&self.directory).join(format!("frame_{frame:04}.{}", self.format.extension()))
+    }
+}
+
+/// The animation being rendered frame by frame.
+pub struct SequenceRender {
+    settings: SequenceSettings,
+    /// The frame being rendered.
+    pub frame: u32,
+}
+
+impl SequenceRender {
+    /// Start rendering at the first frame which hasn't been saved yet,
+    /// so interrupted renders carry on where they stopped.
+    ///
+    /// # Errors
+    ///
+    /// If the folder couldn't be created.
+    pub fn new(settings: &SequenceSettings) -> Result<Self> {
+        std::fs::create_dir_all(&settings.directory)
+            .with_context(|| format!("Failed to create {}", settings.directory))?;
+
+        let mut sequence = Self {
+            settings: settings.clone(),
+            frame: settings.first_frame,
+        };
+        sequence.skip_saved();
+
+        Ok(sequence)
+    }
+
+    fn skip_saved(&mut self) {
+        while !self.is_finished() && self.settings.path(self.frame).exists() {
+            self.frame += 1;
+        }
+    }
+
+    /// Whether every frame has been saved.
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.frame > self.settings.last_frame
+    }
+
+    /// The number of frames in the range and how many are done.
+    #[must_use]
+    pub const fn progress(&self) -> (u32, u32) {
+        let total = (self.settings.last_frame + 1).saturating_sub(self.settings.first_frame);
+        (self.frame.saturating_sub(self.settings.first_frame), total)
+    }
+
+    /// The animation time of the current frame.
+    #[must_use]
+    pub fn time(&self) -> f32 {
+        self.frame.saturating_sub(1) as f32 / self.settings.frame_rate
+    }
+
+    /// Whether the current frame has all its passes.
+    #[must_use]
+    pub const fn is_frame_finished(&self, passes: u32) -> bool {
+        passes >= self.settings.passes
+    }
+
+    /// The format the current frame is saved in.
+    #[must_use]
+    pub const fn format(&self) -> FrameFormat {
+        self.settings.format
+    }
+
+    /// Save the current frame and move onto the next unsaved one,
+    /// returning where it was saved.
+    ///
+    /// # Errors
+    ///
+    /// If the file couldn't be written.
+    pub fn save_frame(&mut self, image: &image::DynamicImage) -> Result<PathBuf> {
+        puffin::profile_function!();
+
+        let path = self.settings.path(self.frame);
+        image
+            .save(&path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        self.frame += 1;
+        self.skip_saved();
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::gpu::{AdaptiveSettings, DenoiseSettings, DisplaySettings};
+use crate::progress::BudgetSettings;
+use crate::sequence::SequenceSettings;
+use crate::tiled::TileSettings;
+
+/// Something the UI asks to be done after the next frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Request {
+    /// Export the AOVs.
+    Export,
+    /// Use the current render as the convergence reference.
+    Reference,
+    /// Start rendering an image in tiles.
+    TiledRender,
+    /// Stop rendering the tiled image.
+    CancelTiledRender,
+    /// Start rendering the animation to numbered images.
+    RenderSequence,
+    /// Stop rendering the animation.
+    CancelSequence,
+}
+
+/// Settings which only change how the render is processed after accumulation,
+/// so changing them doesn't restart the render.
+#[derive(Clone)]
+pub struct Settings {
+    /// The denoiser settings.
+    pub denoise: DenoiseSettings,
+    /// What is displayed.
+    pub display: DisplaySettings,
+    /// Whether the optional AOVs are written.
+    ///
+    /// Changing this does restart the render, so the AOVs are complete.
+    pub aovs: bool,
+    /// How samples are spread between pixels.
+    ///
+    /// Changing this doesn't restart the render either,
+    /// the pixel statistics are kept up to date regardless.
+    pub adaptive: AdaptiveSettings,
+    /// When the render stops accumulating.
+    pub budget: BudgetSettings,
+    /// Where AOVs are exported to, with the AOV name appended.
+    pub export_path: String,
+    /// Where the scene is saved to and opened from.
+    pub scene_path: String,
+    /// How the animation is rendered to numbered images.
+    pub sequence: SequenceSettings,
+    /// How images are rendered in tiles.
+    pub tiles: TileSettings,
+    /// Set by the UI, and taken once it has been done.
+    pub request: Option<Request>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            denoise: DenoiseSettings::default(),
+            display: DisplaySettings::default(),
+            aovs: false,
+            adaptive: AdaptiveSettings::default(),
+            budget: BudgetSettings::default(),
+            export_path: "./render".to_string(),
+            scene_path: "./scene.json".to_string(),
+            sequence: SequenceSettings::default(),
+            tiles: TileSettings::default(),
+            request: None,
+        }
+    }
+}
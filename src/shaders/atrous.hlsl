@@ -0,0 +1,68 @@
+#include "gbuffer.hlsl"
+
+// One iteration of an edge avoiding a-trous wavelet filter.
+// https://jo.dreggn.org/home/2010_atrous.pdf
+
+struct Params {
+  uint step;
+  float colour_phi;
+  float normal_phi;
+  float depth_phi;
+  float albedo_phi;
+  uint width;
+  uint height;
+};
+
+Texture2D<float4> t_input : register(b0);
+StructuredBuffer<GBuffer> gbuffer : register(b1);
+ConstantBuffer<Params> params : register(b2);
+
+// 1D B3 spline kernel, indexed by distance from the center
+static float KERNEL[3] = { 3. / 8., 1. / 4., 1. / 16. };
+
+float4 fs_main(float4 position : SV_POSITION) : SV_TARGET {
+  int2 pixel = int2(position.xy);
+  int width = (int)params.width;
+  int height = (int)params.height;
+
+  GBuffer center = gbuffer[pixel.y * width + pixel.x];
+  float3 center_colour = t_input.Load(int3(pixel, 0)).rgb;
+
+  float3 sum = float3(0., 0., 0.);
+  float weight_sum = 0.;
+
+  for (int y = -2; y <= 2; y += 1) {
+    for (int x = -2; x <= 2; x += 1) {
+      int2 other_pixel = pixel + int2(x, y) * (int)params.step;
+
+      if (
+        other_pixel.x < 0 || other_pixel.y < 0 ||
+        other_pixel.x >= width || other_pixel.y >= height
+      ) {
+        continue;
+      }
+
+      GBuffer other = gbuffer[other_pixel.y * width + other_pixel.x];
+      float3 colour = t_input.Load(int3(other_pixel, 0)).rgb;
+
+      float3 colour_difference = center_colour - colour;
+      float colour_weight = exp(-dot(colour_difference, colour_difference) / params.colour_phi);
+
+      float normal_weight = pow(max(dot(center.normal, other.normal), 0.), params.normal_phi);
+
+      float depth_difference = abs(center.depth - other.depth) / max(center.depth, 0.0001);
+      float depth_weight = exp(-depth_difference / params.depth_phi);
+
+      float3 albedo_difference = center.albedo - other.albedo;
+      float albedo_weight = exp(-dot(albedo_difference, albedo_difference) / params.albedo_phi);
+
+      float weight = KERNEL[abs(x)] * KERNEL[abs(y)]
+        * colour_weight * normal_weight * depth_weight * albedo_weight;
+
+      sum += colour * weight;
+      weight_sum += weight;
+    }
+  }
+
+  return float4(sum / weight_sum, 1.);
+}
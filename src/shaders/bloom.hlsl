@@ -0,0 +1,57 @@
+#include "utils.hlsl"
+
+// One pass of the bloom, which blurs the bright parts of the render
+// by halving them into smaller and smaller textures,
+// then doubling back up through them, blending each into the level above.
+
+struct Params {
+  // matches BloomPass in bloom.rs,
+  // 0 keeps what's over the threshold at half size, 1 halves, 2 doubles
+  uint mode;
+  float threshold;
+  float radius;
+  float exposure;
+};
+
+Texture2D<float4> t_input : register(b0);
+// the level below when upsampling
+Texture2D<float4> t_coarse : register(b1);
+ConstantBuffer<Params> params : register(b2);
+
+// The average of the 2x2 input pixels covered by an output pixel
+float3 downsample(int2 pixel) {
+  uint width;
+  uint height;
+  t_input.GetDimensions(width, height);
+  int2 max_pixel = int2(width, height) - 1;
+
+  float3 sum = float3(0., 0., 0.);
+  for (int y = 0; y < 2; y += 1) {
+    for (int x = 0; x < 2; x += 1) {
+      int2 input_pixel = min(pixel * 2 + int2(x, y), max_pixel);
+      sum += t_input.Load(int3(input_pixel, 0)).rgb;
+    }
+  }
+
+  return sum / 4.;
+}
+
+float4 fs_main(float4 position : SV_POSITION) : SV_TARGET {
+  int2 pixel = int2(position.xy);
+
+  if (params.mode == 0) {
+    float3 colour = downsample(pixel) * params.exposure;
+    // fade in above the threshold, keeping the hue
+    float brightness = max(colour.r, max(colour.g, colour.b));
+    float kept = max(brightness - params.threshold, 0.) / max(brightness, 0.0001);
+    return float4(colour * kept, 1.);
+  }
+
+  if (params.mode == 1) {
+    return float4(downsample(pixel), 1.);
+  }
+
+  float3 current = t_input.Load(int3(pixel, 0)).rgb;
+  float3 below = bilinear(t_coarse, position.xy / 2.);
+  return float4(lerp(current, below, params.radius), 1.);
+}
@@ -0,0 +1,24 @@
+#include "trace.hlsl"
+
+// The render and its stats are read and written in place
+RWTexture2D<float4> t_render : register(b0, space1);
+RWTexture2D<float4> t_stats : register(b3, space1);
+
+[numthreads(8, 8, 1)]
+void cs_main(uint3 id : SV_DispatchThreadID) {
+  if (id.x >= config.width || id.y >= config.height) {
+    return;
+  }
+
+  float4 previous = float4(0.);
+  float4 previous_stats = float4(0.);
+  if (frame_data.progressive_count != 0) {
+    previous = t_render[id.xy];
+    previous_stats = t_stats[id.xy];
+  }
+
+  PixelOutput pixel_output = trace_pixel(id.xy, previous, previous_stats);
+
+  t_render[id.xy] = pixel_output.colour;
+  t_stats[id.xy] = pixel_output.stats;
+}
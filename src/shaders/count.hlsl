@@ -0,0 +1,45 @@
+// Sums the samples and rays of each row of the render,
+// so only one count per row is read back and added up.
+
+struct RowCount {
+  // as 64 bit numbers split into their low and high halves,
+  // as a row's totals can overflow 32 bits
+  uint2 samples;
+  uint2 rays;
+};
+
+Texture2D<float4> t_stats : register(b0);
+StructuredBuffer<uint> ray_counts : register(b1);
+RWStructuredBuffer<RowCount> rows : register(b2);
+
+// Add `value` to a 64 bit number split into its low and high halves
+uint2 add_64(uint2 sum, uint value) {
+  uint low = sum.x + value;
+  uint carry = low < sum.x ? 1 : 0;
+  return uint2(low, sum.y + carry);
+}
+
+[numthreads(64, 1, 1)]
+void cs_main(uint3 id : SV_DispatchThreadID) {
+  uint width;
+  uint height;
+  t_stats.GetDimensions(width, height);
+
+  uint y = id.x;
+  if (y >= height) {
+    return;
+  }
+
+  RowCount count;
+  count.samples = uint2(0, 0);
+  count.rays = uint2(0, 0);
+
+  for (uint x = 0; x < width; x += 1) {
+    // the samples the pixel has taken in total
+    uint samples = (uint)t_stats.Load(int3(x, y, 0)).z;
+    count.samples = add_64(count.samples, samples);
+    count.rays = add_64(count.rays, ray_counts[y * width + x]);
+  }
+
+  rows[y] = count;
+}
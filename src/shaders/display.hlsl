@@ -0,0 +1,87 @@
+#include "gbuffer.hlsl"
+#include "utils.hlsl"
+
+struct Params {
+  uint view;
+  uint width;
+  float depth_range;
+  float exposure;
+  float bloom_intensity;
+};
+
+Texture2D<float4> t_input : register(b0);
+StructuredBuffer<GBuffer> gbuffer : register(b1);
+StructuredBuffer<Aovs> aovs : register(b2);
+ConstantBuffer<Params> params : register(b3);
+Texture2D<float4> t_stats : register(b4);
+// half the size of the render
+Texture2D<float4> t_bloom : register(b5);
+
+// Blue through green to red as t goes from 0 to 1
+float3 heatmap(float t) {
+  t = saturate(t);
+  return saturate(float3(
+    1.5 - abs(4. * t - 3.),
+    1.5 - abs(4. * t - 2.),
+    1.5 - abs(4. * t - 1.)
+  ));
+}
+
+// A distinct colour for each object
+float3 index_colour(int index) {
+  if (index < 0) {
+    return float3(0., 0., 0.);
+  }
+
+  uint hash = (uint)index * 2654435761u;
+  return float3(
+    (float)((hash >> 8) & 255) / 255.,
+    (float)((hash >> 16) & 255) / 255.,
+    (float)((hash >> 24) & 255) / 255.
+  );
+}
+
+float4 fs_main(float4 position : SV_POSITION) : SV_TARGET {
+  int2 pixel = int2(position.xy);
+  uint index = (uint)pixel.y * params.width + (uint)pixel.x;
+
+  float3 colour;
+
+  // matches the order of Aov in aov.rs
+  switch (params.view) {
+    case 1:
+      colour = gbuffer[index].albedo;
+      break;
+    case 2:
+      colour = gbuffer[index].normal * 0.5 + 0.5;
+      break;
+    case 3: {
+      float depth = 1. - saturate(gbuffer[index].depth / params.depth_range);
+      colour = float3(depth, depth, depth);
+      break;
+    }
+    case 4:
+      colour = index_colour(aovs[index].object_index);
+      break;
+    case 5:
+      colour = aovs[index].emission * params.exposure;
+      break;
+    case 6:
+      colour = aovs[index].direct * params.exposure;
+      break;
+    case 7:
+      colour = aovs[index].indirect * params.exposure;
+      break;
+    case 8:
+      colour = heatmap(t_stats.Load(int3(pixel, 0)).w);
+      break;
+    default:
+      colour = t_input.Load(int3(pixel, 0)).rgb * params.exposure;
+      if (params.bloom_intensity > 0.) {
+        colour += bilinear(t_bloom, position.xy / 2.) * params.bloom_intensity;
+      }
+      break;
+  }
+
+  return float4(colour, 1.);
+}
@@ -1,66 +1,28 @@
-#include "inputs.hlsl"
-#include "utils.hlsl"
-#include "random.hlsl"
-#include "ray.hlsl"
+#include "trace.hlsl"
 
-float3 shade(inout Ray ray, Hit hit) {
-  // need to use SampleLevel not Sample because this is done conditionally
-  float3 hdri = t_hdri.SampleLevel(s_tex, float2(
-    0.5 + (atan2(ray.direction.x, ray.direction.z) / (2. * PI)),
-    0.5 + (asin(-ray.direction.y) / PI)),
-    0
-  ).rgb;
+// A copy of last frame's render and stats, as a render target can't be read from
+Texture2D<float4> t_previous : register(b0, space1);
+Texture2D<float4> t_previous_stats : register(b3, space1);
 
-  if (hit.object_index == -1) {
-    ray.energy = float3(0.);
-    return hdri;
-  }
-
-  Material material = objects[hit.object_index].material;
-
-  ray.origin = hit.position + hit.normal * 0.001;
-
-  float3 reflection_ray = reflect(ray.direction, hit.normal);
-  float3 hemisphere_sample = random_in_hemisphere(reflection_ray, material.roughness);
-  ray.direction = hemisphere_sample;
-
-  ray.energy *= (2. * material.colour * clamp(dot(hit.normal, ray.direction), 0., 1.));
-
-  return material.emission * material.emission_strength;
-}
+struct Output {
+  float4 colour : SV_TARGET0;
+  float4 stats : SV_TARGET1;
+};
 
-float3 trace_ray_with_reflections(Ray rayin) {
-  Ray ray = rayin;
-  float3 result = float3(0.);
+Output fs_main(float4 position : SV_POSITION) {
+  int3 pixel = int3(int2(position.xy), 0);
 
-  for (uint i = 0; i < config.reflection_limit; i += 1) {
-    Hit hit = ray_intersect(ray);
-    result += ray.energy * shade(ray, hit);
-
-    if (length(ray.energy) < EPSILON) {
-      break;
-    }
+  float4 previous = float4(0.);
+  float4 previous_stats = float4(0.);
+  if (frame_data.progressive_count != 0) {
+    previous = t_previous.Load(pixel);
+    previous_stats = t_previous_stats.Load(pixel);
   }
 
-  return result;
-}
-
-float4 fs_main(float4 position : SV_POSITION) : SV_TARGET {
-  inputs_init();
-
-  float2 positionJittered = position.xy + frame_data.jitter;
-  float2 coord = position.xy / float2(config.width, config.height);
-  float2 coordJittered = positionJittered / float2(config.width, config.height);
-
-  random_init(coordJittered);
-
-  Ray ray = create_ray(coordJittered);
-  float3 colour = trace_ray_with_reflections(ray);
-
-  float3 previous = t_render.Sample(s_tex, coord);
-  float opacity = 1. / (float)(frame_data.progressive_count + 1);
-  float3 mixed = colour * opacity + previous * (1. - opacity);
-  //
+  PixelOutput pixel_output = trace_pixel(uint2(pixel.xy), previous, previous_stats);
 
-  return float4(mixed, 1.);
+  Output output;
+  output.colour = pixel_output.colour;
+  output.stats = pixel_output.stats;
+  return output;
 }
@@ -0,0 +1,6 @@
+// Draws a single triangle covering the whole screen,
+// so post processing passes don't need a vertex buffer.
+float4 vs_main(uint vertex_index : SV_VertexID) : SV_POSITION {
+  float2 uv = float2((vertex_index << 1) & 2, vertex_index & 2);
+  return float4(uv * 2. - 1., 0., 1.);
+}
@@ -0,0 +1,20 @@
+// The first hit information of a pixel,
+// used by the denoiser to find edges.
+struct GBuffer { // 32
+  float3 albedo; // 12
+  float depth; // 4
+  float3 normal; // 12
+  int _0; // 4
+};
+
+// The optional arbitrary output variables of a pixel.
+// The path's radiance is split by the bounce it came from,
+// so emission + direct + indirect is the full render.
+struct Aovs { // 48
+  float3 emission; // 12
+  int object_index; // 4
+  float3 direct; // 12
+  int _0; // 4
+  float3 indirect; // 12
+  int _1; // 4
+};
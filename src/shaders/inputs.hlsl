@@ -1,3 +1,5 @@
+#include "gbuffer.hlsl"
+
 struct Material { // 48
   float3 colour; // 12
   int _0;// 4
@@ -8,18 +10,21 @@ struct Material { // 48
   int _1[2]; // 8
 };
 
-struct Geometry { // 56
+struct Geometry { // 64
   uint option; // 4
   int _0[3]; // 12
   float3 center; // 16
   float3 vec_data; // 12
   float f32_data; // 4
-  uint data[2]; // 8
+  float3 vec_data_2; // 16
 };
 
-struct Object { // 112
+struct Object { // 128
   Material material; // 48
   Geometry geometry; // 64
+  // units per second the object moves while the shutter is open
+  float3 velocity; // 12
+  int _0; // 4
 };
 
 struct Light {
@@ -47,11 +52,56 @@ struct Config {
   uint reflection_limit;
   uint width;
   uint height;
+  uint use_hdri;
+  uint seed;
+  uint sampler_kind;
+  uint russian_roulette;
+  uint roulette_depth;
+  uint clamp_radiance;
+  float clamp_direct;
+  float clamp_indirect;
+  uint path_roughening;
+  // the render is a tile of the full image,
+  // width and height are the size of the tile
+  uint2 tile_offset;
+  uint2 full_size;
+  // seconds from the animation time to when the shutter opens and closes
+  float shutter_open; // 160
+  float shutter_close; // 164
+  // pads position_end to a 16 byte boundary
+  int _5; // 168
+  int _6; // 172
+  // the camera as the shutter closes,
+  // the camera above is as it opens
+  float3 position_end; // 176
+  int _7; // 188
+  float3 forward_end; // 192
+  int _8; // 204
+  float3 right_end; // 208
+  int _9; // 220
+  float3 up_end; // 224
+  int _10; // 236
+  // 0 perspective, 1 orthographic, 2 equirectangular, 3 fisheye
+  uint projection; // 240
+  float ortho_width; // 244
+  // 0 off, 1 side by side, 2 top and bottom
+  uint stereo; // 248
+  float eye_separation; // 252
+  // a thin lens, with a radius of 0 for a pinhole
+  float lens_radius; // 256
+  float focus_distance; // 260
+  // pads the struct to a multiple of 16 bytes, 272
+  uint2 _11; // 264
 };
 
 struct FrameData {
-  float2 jitter;
   uint progressive_count;
+  uint write_aovs;
+  uint adaptive;
+  float threshold;
+  uint min_samples;
+  uint max_samples;
+  uint2 _0;
 };
 
 // difference between StructuredBuffer and ConstantBuffer
@@ -68,9 +118,15 @@ StructuredBuffer<Light> lights : register(b1);
 ConstantBuffer<Config> config : register(b2);
 SamplerState s_tex : register(b3);
 Texture2D<float4> t_hdri : register(b4);
-Texture2D<float4> t_render : register(b5);
-Texture2D<float4> t_random : register(b6);
-ConstantBuffer<FrameData> frame_data : register(b7);
+ConstantBuffer<FrameData> frame_data : register(b5);
+
+// These depend on the render size, so are in a separate group.
+// Bindings 0 and 3 hold the render and its stats,
+// which are declared by each tracer as they are bound differently.
+RWStructuredBuffer<GBuffer> gbuffer : register(b1, space1);
+RWStructuredBuffer<Aovs> aovs : register(b2, space1);
+// the rays each pixel has traced in total
+RWStructuredBuffer<uint> ray_counts : register(b4, space1);
 
 static uint object_count;
 
@@ -1,13 +1,42 @@
-static float rng_seed = 42.;
-static float2 rng_pixel = float2(0., 0.);
+// A stateless hash based random number generator,
+// so every pixel, frame, sample and bounce gets an independent stream
+// and renders with the same seed are identical.
+// https://www.reedbeta.com/blog/hash-functions-for-gpu-rendering/
 
-float random() {
-  float2 coord = (rng_pixel + rng_seed) % 1.0;
+static uint rng_path_seed = 0;
+static uint rng_state = 0;
+
+uint pcg_hash(uint input) {
+  uint state = input * 747796405u + 2891336453u;
+  uint word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+  return (word >> 22u) ^ word;
+}
+
+// Seed the path traced from `pixel` for the given sample of the given frame
+void random_init(uint2 pixel, uint frame, uint sample_index) {
+  uint seed = pcg_hash(config.seed);
+  seed = pcg_hash(seed ^ pixel.x);
+  seed = pcg_hash(seed ^ pixel.y);
+  seed = pcg_hash(seed ^ frame);
+  rng_path_seed = pcg_hash(seed ^ sample_index);
+  rng_state = rng_path_seed;
+}
+
+// Start a new stream for the given bounce of the path
+void random_bounce(uint bounce) {
+  rng_state = pcg_hash(rng_path_seed ^ pcg_hash(bounce));
+}
+
+// Map a random uint to [0, 1)
+float to_unit_float(uint x) {
+  // the top 24 bits fit exactly in a float
+  return (float)(x >> 8) / 16777216.;
+}
 
-  int size = config.width * config.height;
-  rng_seed += t_random.SampleLevel(s_tex, coord, 0).x * 532.3412;
-  rng_seed %= 600;
-  return t_random.SampleLevel(s_tex, coord, 0).x;
+// A uniform random number in [0, 1)
+float random() {
+  rng_state = pcg_hash(rng_state);
+  return to_unit_float(rng_state);
 }
 
 float3x3 get_tangent_space(float3 normal) {
@@ -22,7 +51,8 @@ float3x3 get_tangent_space(float3 normal) {
   return float3x3(tangent, binormal, normal);
 }
 
-float3 random_in_hemisphere(float3 normal, float roughness) {
+// `u` is a point in [0, 1)^2
+float3 random_in_hemisphere(float3 normal, float roughness, float2 u) {
   if (roughness == 0.) {
     return normal;
   }
@@ -30,9 +60,9 @@ float3 random_in_hemisphere(float3 normal, float roughness) {
   float smoothness = 1. - roughness;
   float phong_alpha = pow(1000., smoothness * smoothness);
 
-  float cos_theta = pow(random(), 1. / (phong_alpha + 1.));
+  float cos_theta = pow(u.x, 1. / (phong_alpha + 1.));
   float sin_theta = sqrt(1. - (cos_theta * cos_theta));
-  float phi = 2. * PI * random();
+  float phi = 2. * PI * u.y;
   float3 tangent_space_dir = float3(
     cos(phi) * sin_theta,
     sin(phi) * sin_theta,
@@ -41,7 +71,3 @@ float3 random_in_hemisphere(float3 normal, float roughness) {
 
   return get_tangent_space(normal) * tangent_space_dir;
 }
-
-void random_init(float2 pixel) {
-  rng_pixel = pixel;
-}
@@ -2,6 +2,8 @@ struct Ray {
   float3 origin;
   float3 direction;
   float3 energy;
+  // when the ray is traced, from 0 as the shutter opens to 1 as it closes
+  float time;
 };
 
 struct Hit {
@@ -11,37 +13,96 @@ struct Hit {
   int object_index;
 };
 
-Ray create_ray(float2 coord) {
-  // calculate the viewport dimensions
-  float fov_rad = config.fov * PI / 180.;
-  float half_width = tan(fov_rad / 2.);
-  float half_height = half_width * (float)config.height / (float)config.width;
+Ray create_ray(float2 coord, float time, float2 lens) {
+  // the camera moves while the shutter is open
+  float3 position = lerp(config.position, config.position_end, time);
+  float3 forward = normalize(lerp(config.forward, config.forward_end, time));
+  float3 right_vector = normalize(lerp(config.right, config.right_end, time));
+  float3 up = normalize(lerp(config.up, config.up_end, time));
+
+  // which eye sees the pixel, -1 for the left, 1 for the right or 0 without stereo,
+  // and where it is in that eye's part of the image
+  float eye = 0.;
+  float2 eye_size = float2(config.full_size);
+  if (config.stereo == 1) {
+    eye = coord.x < 0.5 ? -1. : 1.;
+    coord.x = frac(coord.x * 2.);
+    eye_size.x /= 2.;
+  } else if (config.stereo == 2) {
+    eye = coord.y < 0.5 ? -1. : 1.;
+    coord.y = frac(coord.y * 2.);
+    eye_size.y /= 2.;
+  }
 
-  float3 center = config.position - config.forward;
+  // from -1 to 1 across the width, with the same scale up the height
+  float aspect = eye_size.x / eye_size.y;
+  float2 offset = float2(coord.x * 2. - 1., (1. - coord.y * 2.) / aspect);
 
-  float3 left = center - (config.right * half_width);
-  float3 right = center + (config.right * half_width);
-  float3 bottom = center - (config.up * half_height);
-  float3 top = center + (config.up * half_height);
+  float3 origin = position;
+  float3 direction;
+  float3 energy = float3(1.);
+  // the eyes are apart along this axis
+  float3 eye_axis = right_vector;
+
+  if (config.projection == 1) {
+    // orthographic
+    origin += (right_vector * offset.x + up * offset.y) * config.ortho_width / 2.;
+    direction = -forward;
+  } else if (config.projection == 2) {
+    // equirectangular
+    float longitude = offset.x * PI;
+    float latitude = (1. - coord.y * 2.) * PI / 2.;
+    direction = right_vector * sin(longitude) * cos(latitude)
+      + up * sin(latitude)
+      - forward * cos(longitude) * cos(latitude);
+    // omni-directional stereo, the eyes turn with the direction looked in
+    eye_axis = right_vector * cos(longitude) + forward * sin(longitude);
+  } else if (config.projection == 3) {
+    // equidistant fisheye, the angle grows evenly from the centre
+    float radius = length(offset);
+    float angle = radius * config.fov * PI / 360.;
+    float3 across = float3(0.);
+    if (radius > 0.) {
+      across = (right_vector * offset.x + up * offset.y) / radius;
+    }
+    direction = -forward * cos(angle) + across * sin(angle);
 
-  float3 top_left = left + top - center;
-  float width = half_width * 2.;
-  float height = half_height * 2.;
+    // nothing is seen outside the circle
+    if (radius > 1.) {
+      energy = float3(0.);
+    }
+  } else {
+    // perspective
+    float half_width = tan(config.fov * PI / 360.);
+    direction = -forward + (right_vector * offset.x + up * offset.y) * half_width;
+
+    // a thin lens, everything on the plane at the focus distance stays sharp
+    if (config.lens_radius > 0.) {
+      float3 focus = origin + direction * config.focus_distance;
+      float2 disk = sample_disk(lens) * config.lens_radius;
+      origin += right_vector * disk.x + up * disk.y;
+      direction = focus - origin;
+    }
+  }
 
-  // create ray
-  float3 x_offset = config.right * (coord.x * width);
-  float3 y_offset = -config.up * (coord.y * height);
+  origin += eye_axis * eye * config.eye_separation / 2.;
 
-  float3 pixel_world_space = top_left + x_offset + y_offset;
+  return Ray(origin, normalize(direction), energy, time);
+}
 
-  return Ray(config.position, normalize(pixel_world_space - config.position), float3(1.));
+// Where the object is at the ray's time
+float3 object_center(Object object, float time) {
+  float seconds = lerp(config.shutter_open, config.shutter_close, time);
+  return object.geometry.center + object.velocity * seconds;
 }
 
-float3 object_normal(Object object, float3 position) {
+float3 object_normal(Object object, float3 position, float time) {
   if (object.geometry.option == 0) {
-    return normalize(position - object.geometry.center);
+    return normalize(position - object_center(object, time));
   } if (object.geometry.option == 1) {
     return object.geometry.vec_data;
+  } if (object.geometry.option == 2) {
+    return normalize(cross(object.geometry.vec_data, object.geometry.vec_data_2));
   }
 
   return float3(0., 1., 0.);
@@ -49,9 +110,10 @@ float3 object_normal(Object object, float3 position) {
 
 void object_intersect(inout Hit hit, uint i, Ray ray) {
   Object object = objects[i];
+  float3 center = object_center(object, ray.time);
 
   if (object.geometry.option == 0) {
-    float3 new_origin = ray.origin - object.geometry.center;
+    float3 new_origin = ray.origin - center;
 
     float a = 1.;
     float b = 2. * dot(ray.direction, new_origin);
@@ -71,15 +133,15 @@ void object_intersect(inout Hit hit, uint i, Ray ray) {
 
     if (abs(denominator) < EPSILON) { return; }
 
-    float numerator = dot(object.geometry.center - ray.origin, object.geometry.vec_data);
+    float numerator = dot(center - ray.origin, object.geometry.vec_data);
     float distance = numerator / denominator;
 
     float3 hit_point = ray.origin + (ray.direction * distance);
 
     if (
-      abs(hit_point.x - object.geometry.center.x) > object.geometry.f32_data |
-      abs(hit_point.y - object.geometry.center.y) > object.geometry.f32_data |
-      abs(hit_point.z - object.geometry.center.z) > object.geometry.f32_data
+      abs(hit_point.x - center.x) > object.geometry.f32_data |
+      abs(hit_point.y - center.y) > object.geometry.f32_data |
+      abs(hit_point.z - center.z) > object.geometry.f32_data
     ) {
       return;
     }
@@ -91,6 +153,30 @@ void object_intersect(inout Hit hit, uint i, Ray ray) {
       hit.position = hit_point;
       hit.object_index = (int)i;
     }
+  } else if (object.geometry.option == 2) {
+    float3 u = object.geometry.vec_data;
+    float3 v = object.geometry.vec_data_2;
+    float3 normal = cross(u, v);
+
+    float denominator = dot(ray.direction, normal);
+
+    if (abs(denominator) < EPSILON) { return; }
+
+    float distance = dot(center - ray.origin, normal) / denominator;
+
+    if (distance < EPSILON || distance >= hit.distance) { return; }
+
+    float3 hit_point = ray.origin + (ray.direction * distance);
+    float3 offset = hit_point - center;
+
+    // project onto each edge, 1 being the edge of the quad
+    if (abs(dot(offset, u)) > dot(u, u) || abs(dot(offset, v)) > dot(v, v)) {
+      return;
+    }
+
+    hit.distance = distance;
+    hit.position = hit_point;
+    hit.object_index = (int)i;
   }
 }
 
@@ -101,7 +187,12 @@ Hit ray_intersect(Ray ray) {
     object_intersect(hit, i, ray);
   }
 
-  hit.normal = object_normal(objects[hit.object_index], hit.position);
+  hit.normal = object_normal(objects[hit.object_index], hit.position, ray.time);
+
+  // planes and quads can be hit from either side
+  if (dot(hit.normal, ray.direction) > 0.) {
+    hit.normal = -hit.normal;
+  }
 
   return hit;
 }
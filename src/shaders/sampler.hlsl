@@ -0,0 +1,158 @@
+// Sample points for each pixel, indexed by pixel, sample number and dimension,
+// generated with the strategy chosen in `config.sampler_kind`.
+//
+// Each dimension is a pair of numbers used together,
+// such as the two coordinates of the jitter within the pixel.
+
+static uint SAMPLER_INDEPENDENT = 0;
+static uint SAMPLER_STRATIFIED = 1;
+static uint SAMPLER_SOBOL = 2;
+static uint SAMPLER_R2 = 3;
+
+// The dimensions consumed by each part of a path
+static uint SAMPLE_DIM_JITTER = 0;
+static uint SAMPLE_DIM_LENS = 1;
+static uint SAMPLE_DIM_TIME = 2;
+
+uint sample_dim_bsdf(uint bounce) {
+  return 3 + bounce * 2;
+}
+
+uint sample_dim_light(uint bounce) {
+  return 4 + bounce * 2;
+}
+
+// The strata along each axis for stratified sampling
+static uint STRATA = 16;
+
+static uint2 sampler_pixel = uint2(0, 0);
+static uint sampler_index = 0;
+static uint sampler_pixel_seed = 0;
+
+// Set the pixel and the sample number within it
+void sampler_init(uint2 pixel, uint index) {
+  sampler_pixel = pixel;
+  sampler_index = index;
+  sampler_pixel_seed = pcg_hash(pcg_hash(pcg_hash(config.seed) ^ pixel.x) ^ pixel.y);
+}
+
+float2 hash_2d(uint seed) {
+  uint x = pcg_hash(seed);
+  return float2(to_unit_float(x), to_unit_float(pcg_hash(x)));
+}
+
+// A random permutation of i within [0, count)
+// Kensler 2013, Correlated Multi-Jittered Sampling
+uint permute(uint i, uint count, uint seed) {
+  uint w = count - 1;
+  w |= w >> 1;
+  w |= w >> 2;
+  w |= w >> 4;
+  w |= w >> 8;
+  w |= w >> 16;
+
+  do {
+    i ^= seed;
+    i *= 0xe170893du;
+    i ^= seed >> 16;
+    i ^= (i & w) >> 4;
+    i ^= seed >> 8;
+    i *= 0x0929eb3fu;
+    i ^= seed >> 23;
+    i ^= (i & w) >> 1;
+    i *= 1 | seed >> 27;
+    i *= 0x6935fa69u;
+    i ^= (i & w) >> 11;
+    i *= 0x74dcb303u;
+    i ^= (i & w) >> 2;
+    i *= 0x9e501cc3u;
+    i ^= (i & w) >> 2;
+    i *= 0xc860a3dfu;
+    i &= w;
+    i ^= i >> 5;
+  } while (i >= count);
+
+  return (i + seed) % count;
+}
+
+// Each run of STRATA * STRATA samples covers every stratum once, in a random order
+float2 stratified_2d(uint seed) {
+  uint count = STRATA * STRATA;
+  uint run_seed = pcg_hash(seed ^ (sampler_index / count));
+  uint stratum = permute(sampler_index % count, count, run_seed);
+
+  float2 offset = hash_2d(run_seed ^ sampler_index);
+  return (float2(stratum % STRATA, stratum / STRATA) + offset) / (float)STRATA;
+}
+
+// Burley 2020, Practical Hash-based Owen Scrambling
+uint laine_karras_permutation(uint x, uint seed) {
+  x += seed;
+  x ^= x * 0x6c50b47cu;
+  x ^= x * 0xb82f1e52u;
+  x ^= x * 0xc7afe638u;
+  x ^= x * 0x8d22f6e6u;
+  return x;
+}
+
+uint nested_uniform_scramble(uint x, uint seed) {
+  return reversebits(laine_karras_permutation(reversebits(x), seed));
+}
+
+// The first two dimensions of the Sobol sequence
+uint2 sobol_2d(uint index) {
+  uint2 result = uint2(0, 0);
+  uint v = 1u << 31;
+
+  for (uint i = 0; index != 0; i += 1) {
+    if ((index & 1) != 0) {
+      result ^= uint2(1u << (31 - i), v);
+    }
+    index >>= 1;
+    v ^= v >> 1;
+  }
+
+  return result;
+}
+
+// Each dimension shuffles the sequence differently,
+// so dimensions aren't correlated with each other
+float2 sobol_owen_2d(uint seed) {
+  uint index = nested_uniform_scramble(sampler_index, pcg_hash(seed));
+  uint2 p = sobol_2d(index);
+
+  p.x = nested_uniform_scramble(p.x, pcg_hash(seed ^ 0xa511e9b3u));
+  p.y = nested_uniform_scramble(p.y, pcg_hash(seed ^ 0x63d83595u));
+
+  return float2(to_unit_float(p.x), to_unit_float(p.y));
+}
+
+// Roberts 2018, The Unreasonable Effectiveness of Quasirandom Sequences
+static float2 R2_ALPHA = float2(0.7548776662466927, 0.5698402909980532);
+// R2_ALPHA as 32 bit fixed point, so large indices don't lose precision
+static uint2 R2_ALPHA_FIXED = uint2(3242174889u, 2447445413u);
+
+// The R2 sequence, offset for each pixel by the R2 dither mask,
+// which has a blue noise like spectrum, and rotated randomly for each dimension
+float2 r2_2d(uint dimension) {
+  uint2 p = R2_ALPHA_FIXED * sampler_index;
+  float dither = frac(dot(float2(sampler_pixel), R2_ALPHA));
+  float2 rotation = hash_2d(pcg_hash(config.seed) ^ pcg_hash(dimension));
+
+  return frac(float2(to_unit_float(p.x), to_unit_float(p.y)) + dither + rotation);
+}
+
+// A point in [0, 1)^2 for the given dimension of the current sample
+float2 sample_2d(uint dimension) {
+  uint seed = pcg_hash(sampler_pixel_seed ^ pcg_hash(dimension));
+
+  if (config.sampler_kind == SAMPLER_STRATIFIED) {
+    return stratified_2d(seed);
+  } else if (config.sampler_kind == SAMPLER_SOBOL) {
+    return sobol_owen_2d(seed);
+  } else if (config.sampler_kind == SAMPLER_R2) {
+    return r2_2d(dimension);
+  }
+
+  return float2(random(), random());
+}
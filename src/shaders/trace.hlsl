@@ -0,0 +1,280 @@
+// The path tracing shared by the compute and fragment tracers.
+
+#include "inputs.hlsl"
+#include "utils.hlsl"
+#include "random.hlsl"
+#include "sampler.hlsl"
+#include "ray.hlsl"
+
+float3 environment(float3 direction) {
+  if (config.use_hdri == 0) {
+    return config.background_colour;
+  }
+
+  // need to use SampleLevel not Sample because this is done conditionally
+  return t_hdri.SampleLevel(s_tex, float2(
+    0.5 + (atan2(direction.x, direction.z) / (2. * PI)),
+    0.5 + (asin(-direction.y) / PI)),
+    0
+  ).rgb;
+}
+
+// `path_roughness` is the roughest surface the path has bounced off so far
+float3 shade(inout Ray ray, Hit hit, uint bounce, inout float path_roughness) {
+  if (hit.object_index == -1) {
+    ray.energy = float3(0.);
+    return environment(ray.direction);
+  }
+
+  Material material = objects[hit.object_index].material;
+
+  float roughness = material.roughness;
+  if (config.path_roughening != 0) {
+    roughness = max(roughness, path_roughness);
+    path_roughness = roughness;
+  }
+
+  ray.origin = hit.position + hit.normal * 0.001;
+
+  float3 reflection_ray = reflect(ray.direction, hit.normal);
+  float3 hemisphere_sample = random_in_hemisphere(
+    reflection_ray,
+    roughness,
+    sample_2d(sample_dim_bsdf(bounce))
+  );
+  ray.direction = hemisphere_sample;
+
+  ray.energy *= (2. * material.colour * clamp(dot(hit.normal, ray.direction), 0., 1.));
+
+  return material.emission * material.emission_strength;
+}
+
+GBuffer first_hit_info(Ray ray, Hit hit) {
+  GBuffer info;
+  info._0 = 0;
+
+  if (hit.object_index == -1) {
+    info.albedo = environment(ray.direction);
+    info.normal = -ray.direction;
+    info.depth = hit.distance;
+  } else {
+    info.albedo = objects[hit.object_index].material.colour;
+    info.normal = hit.normal;
+    // distance along the view axis, not along the ray
+    info.depth = hit.distance * dot(ray.direction, -config.forward);
+  }
+
+  return info;
+}
+
+// Scale down a sample's light so its brightest channel is at most `limit`
+float3 clamp_radiance(float3 radiance, float limit) {
+  float brightest = max(radiance.r, max(radiance.g, radiance.b));
+  if (brightest <= limit) {
+    return radiance;
+  }
+  return radiance * (limit / brightest);
+}
+
+// `rays` is increased by every ray traced along the path
+float3 trace_ray_with_reflections(Ray rayin, out GBuffer first_hit, out Aovs path_aovs, inout uint rays) {
+  Ray ray = rayin;
+  float3 result = float3(0.);
+
+  path_aovs.emission = float3(0.);
+  path_aovs.object_index = -1;
+  path_aovs.direct = float3(0.);
+  path_aovs._0 = 0;
+  path_aovs.indirect = float3(0.);
+  path_aovs._1 = 0;
+
+  float path_roughness = 0.;
+
+  for (uint i = 0; i < config.reflection_limit; i += 1) {
+    random_bounce(i);
+
+    Hit hit = ray_intersect(ray);
+    rays += 1;
+
+    if (i == 0) {
+      first_hit = first_hit_info(ray, hit);
+      path_aovs.object_index = hit.object_index;
+    }
+
+    float3 contribution = ray.energy * shade(ray, hit, i, path_roughness);
+
+    // light seen directly by the camera is never clamped
+    if (config.clamp_radiance != 0 && i == 1) {
+      contribution = clamp_radiance(contribution, config.clamp_direct);
+    } else if (config.clamp_radiance != 0 && i > 1) {
+      contribution = clamp_radiance(contribution, config.clamp_indirect);
+    }
+
+    result += contribution;
+
+    if (i == 0) {
+      path_aovs.emission += contribution;
+    } else if (i == 1) {
+      path_aovs.direct += contribution;
+    } else {
+      path_aovs.indirect += contribution;
+    }
+
+    if (length(ray.energy) < EPSILON) {
+      break;
+    }
+
+    // end dim paths randomly, boosting the survivors to make up for the ones ended
+    if (config.russian_roulette != 0 && i + 1 >= config.roulette_depth) {
+      float survival = saturate(max(ray.energy.r, max(ray.energy.g, ray.energy.b)));
+      if (random() >= survival) {
+        break;
+      }
+      ray.energy /= survival;
+    }
+  }
+
+  return result;
+}
+
+struct PixelOutput {
+  float4 colour;
+  // x: mean luminance
+  // y: sum of squared differences from the mean
+  // z: samples taken in total
+  // w: samples taken this frame, as a fraction of the most allowed
+  float4 stats;
+};
+
+float luminance(float3 colour) {
+  return dot(colour, float3(0.2126, 0.7152, 0.0722));
+}
+
+// How many samples to take this frame,
+// based on the estimated error of the pixel so far.
+uint samples_this_frame(float4 stats) {
+  if (frame_data.adaptive == 0) {
+    return 1;
+  }
+
+  float n = stats.z;
+
+  // the variance needs at least two samples
+  if (n < (float)max(frame_data.min_samples, 2)) {
+    return 1;
+  }
+
+  float variance = stats.y / (n - 1.);
+  // relative standard error of the mean
+  float error = sqrt(variance / n) / max(stats.x, 0.001);
+
+  if (error < frame_data.threshold) {
+    return 0;
+  }
+
+  return clamp((uint)ceil(error / frame_data.threshold), 1, frame_data.max_samples);
+}
+
+// Trace this frame's samples for `pixel` within the tile
+// and mix them with the previous frames.
+// `previous` and `previous_stats` are zero on the first frame.
+PixelOutput trace_pixel(uint2 pixel, float4 previous, float4 previous_stats) {
+  inputs_init();
+
+  uint index = pixel.y * config.width + pixel.x;
+  // the pixel within the full image, so tiles match an untiled render
+  uint2 image_pixel = config.tile_offset + pixel;
+
+  uint samples = samples_this_frame(previous_stats);
+
+  PixelOutput output;
+
+  // the pixel has converged
+  if (samples == 0) {
+    output.colour = previous;
+    output.stats = float4(previous_stats.xyz, 0.);
+    return output;
+  }
+
+  float2 size = float2(config.full_size);
+
+  float3 colour_sum = float3(0.);
+  GBuffer first_hit;
+  Aovs aovs_sum;
+
+  float mean = previous_stats.x;
+  float m2 = previous_stats.y;
+  float n = previous_stats.z;
+  uint rays = 0;
+
+  for (uint s = 0; s < samples; s += 1) {
+    random_init(image_pixel, frame_data.progressive_count, s);
+    // every sample the pixel has taken, so sequences carry on across frames
+    sampler_init(image_pixel, (uint)previous_stats.z + s);
+
+    // anywhere within the pixel
+    float2 jitter = sample_2d(SAMPLE_DIM_JITTER) - 0.5;
+
+    // anywhere within the shutter interval
+    float time = sample_2d(SAMPLE_DIM_TIME).x;
+
+    // anywhere on the aperture
+    float2 lens = sample_2d(SAMPLE_DIM_LENS);
+
+    Ray ray = create_ray((float2(image_pixel) + 0.5 + jitter) / size, time, lens);
+    GBuffer sample_hit;
+    Aovs sample_aovs;
+    float3 colour = trace_ray_with_reflections(ray, sample_hit, sample_aovs, rays);
+
+    colour_sum += colour;
+
+    if (s == 0) {
+      first_hit = sample_hit;
+      aovs_sum = sample_aovs;
+    } else {
+      aovs_sum.emission += sample_aovs.emission;
+      aovs_sum.direct += sample_aovs.direct;
+      aovs_sum.indirect += sample_aovs.indirect;
+    }
+
+    // Welford's online algorithm
+    n += 1.;
+    float value = luminance(colour);
+    float delta = value - mean;
+    mean += delta / n;
+    m2 += delta * (value - mean);
+  }
+
+  // the new samples' share of all the samples taken
+  float weight = (float)samples / n;
+
+  output.colour = float4(lerp(previous.rgb, colour_sum / (float)samples, weight), 1.);
+  output.stats = float4(mean, m2, n, (float)samples / (float)max(frame_data.max_samples, 1));
+
+  if (frame_data.progressive_count != 0) {
+    rays += ray_counts[index];
+  }
+  ray_counts[index] = rays;
+
+  // the gbuffer is only used as a guide, so one sample a frame is enough
+  float opacity = 1. / (float)(frame_data.progressive_count + 1);
+  GBuffer previous_hit = gbuffer[index];
+  first_hit.albedo = lerp(previous_hit.albedo, first_hit.albedo, opacity);
+  first_hit.normal = normalize(lerp(previous_hit.normal, first_hit.normal, opacity));
+  first_hit.depth = lerp(previous_hit.depth, first_hit.depth, opacity);
+  gbuffer[index] = first_hit;
+
+  if (frame_data.write_aovs != 0) {
+    Aovs previous_aovs = aovs[index];
+    aovs_sum.emission = lerp(previous_aovs.emission, aovs_sum.emission / (float)samples, weight);
+    aovs_sum.direct = lerp(previous_aovs.direct, aovs_sum.direct / (float)samples, weight);
+    aovs_sum.indirect = lerp(previous_aovs.indirect, aovs_sum.indirect / (float)samples, weight);
+    // an index can't be averaged, so keep the first one
+    if (frame_data.progressive_count != 0) {
+      aovs_sum.object_index = previous_aovs.object_index;
+    }
+    aovs[index] = aovs_sum;
+  }
+
+  return output;
+}
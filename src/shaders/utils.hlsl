@@ -14,3 +14,51 @@ float solve_quadratic(float a, float b, float c) {
 
   return minus;
 }
+
+// Maps a point in the unit square to the unit disk,
+// with the concentric mapping so stratified points stay stratified
+float2 sample_disk(float2 square) {
+  float2 offset = square * 2. - 1.;
+
+  if (offset.x == 0. && offset.y == 0.) {
+    return float2(0., 0.);
+  }
+
+  float radius;
+  float theta;
+  if (abs(offset.x) > abs(offset.y)) {
+    radius = offset.x;
+    theta = (PI / 4.) * (offset.y / offset.x);
+  } else {
+    radius = offset.y;
+    theta = (PI / 2.) - (PI / 4.) * (offset.x / offset.y);
+  }
+
+  return radius * float2(cos(theta), sin(theta));
+}
+
+// The texture filtered between its pixels,
+// where position is in its pixels and the edges are clamped
+float3 bilinear(Texture2D<float4> t, float2 position) {
+  uint width;
+  uint height;
+  t.GetDimensions(width, height);
+  int2 max_pixel = int2(width, height) - 1;
+
+  float2 corner = position - 0.5;
+  int2 base = int2(floor(corner));
+  float2 f = corner - floor(corner);
+
+  float3 top = lerp(
+    t.Load(int3(clamp(base, int2(0, 0), max_pixel), 0)).rgb,
+    t.Load(int3(clamp(base + int2(1, 0), int2(0, 0), max_pixel), 0)).rgb,
+    f.x
+  );
+  float3 bottom = lerp(
+    t.Load(int3(clamp(base + int2(0, 1), int2(0, 0), max_pixel), 0)).rgb,
+    t.Load(int3(clamp(base + int2(1, 1), int2(0, 0), max_pixel), 0)).rgb,
+    f.x
+  );
+
+  return lerp(top, bottom, f.y);
+}
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::gpu::{GpuTimings, SampleCounts};
+
+/// The statistics of one frame.
+pub struct FrameStatistics {
+    pub time: Instant,
+    /// The sample counts which arrived this frame, which are for an earlier frame.
+    pub counts: Option<SampleCounts>,
+    /// Averaged over the counts which arrived in the last second.
+    pub rays_per_second: f64,
+    /// The GPU timings which arrived this frame, which are for an earlier frame.
+    pub timings: Option<GpuTimings>,
+}
+
+/// The recent history of how fast the render is accumulating.
+#[derive(Default)]
+pub struct Statistics {
+    /// Whether the GPU can time its passes, otherwise there are never any timings.
+    pub gpu_timing: bool,
+    history: VecDeque<FrameStatistics>,
+    /// The counts of the current render last read back,
+    /// kept after they leave the history as they stop arriving once the render is final.
+    counts: Option<SampleCounts>,
+}
+
+impl Statistics {
+    /// How far back the history goes, the same as the fps history.
+    const HISTORY: Duration = Duration::from_secs(5);
+    /// How far back the rays per second are averaged over.
+    const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+    /// The frames within the history, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &FrameStatistics> {
+        self.history.iter()
+    }
+
+    /// The latest frame recorded.
+    #[must_use]
+    pub fn latest(&self) -> Option<&FrameStatistics> {
+        self.history.back()
+    }
+
+    /// The most recent sample counts of the current render.
+    #[must_use]
+    pub const fn latest_counts(&self) -> Option<SampleCounts> {
+        self.counts
+    }
+
+    /// The most recent GPU timings which arrived.
+    #[must_use]
+    pub fn latest_timings(&self) -> Option<GpuTimings> {
+        self.history.iter().rev().find_map(|frame| frame.timings)
+    }
+
+    /// Forget the counts of the last render, as the render was restarted.
+    pub const fn restart(&mut self) {
+        self.counts = None;
+    }
+
+    /// Record a frame, forgetting frames older than the history.
+    pub fn record(&mut self, counts: Option<SampleCounts>, timings: Option<GpuTimings>) {
+        let time = Instant::now();
+
+        while self
+            .history
+            .front()
+            .is_some_and(|frame| time - frame.time > Self::HISTORY)
+        {
+            self.history.pop_front();
+        }
+
+        let rays_per_second = self.rays_per_second(time, counts);
+        if counts.is_some() {
+            self.counts = counts;
+        }
+
+        self.history.push_back(FrameStatistics {
+            time,
+            counts,
+            rays_per_second,
+            timings,
+        });
+    }
+
+    /// The rays traced per second between the counts which arrived
+    /// over the last [`Statistics::RATE_WINDOW`], up to a frame at `time` with `counts`.
+    fn rays_per_second(&self, time: Instant, counts: Option<SampleCounts>) -> f64 {
+        let mut recent = self
+            .history
+            .iter()
+            .filter(|frame| time - frame.time <= Self::RATE_WINDOW)
+            .filter_map(|frame| Some((frame.time, frame.counts?.rays)))
+            .chain(counts.map(|counts| (time, counts.rays)))
+            .peekable();
+
+        let Some(&(start, _)) = recent.peek() else {
+            return 0.;
+        };
+
+        let mut rays = 0;
+        let mut last = None;
+        for (arrived, total) in recent {
+            if let Some(last_total) = last.map(|(_, total)| total) {
+                // the total goes back to zero when the render restarts
+                rays += if total >= last_total {
+                    total - last_total
+                } else {
+                    total
+                };
+            }
+            last = Some((arrived, total));
+        }
+
+        let seconds = last.map_or(0., |(end, _)| (end - start).as_secs_f64());
+        if seconds > 0. {
+            rays as f64 / seconds
+        } else {
+            0.
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::ray_tracer::Viewport;
+
+/// Settings for rendering an image in tiles,
+/// which can be larger than the GPU's largest texture.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TileSettings {
+    /// The size of the full image.
+    pub size: (u32, u32),
+    /// The largest size of each tile.
+    pub tile_size: u32,
+    /// The passes over the image each tile is rendered with.
+    pub passes: u32,
+    /// Where the image is saved.
+    pub path: String,
+}
+
+impl Default for TileSettings {
+    fn default() -> Self {
+        Self {
+            size: (7680, 4320),
+            tile_size: 512,
+            passes: 256,
+            path: "./render_tiled.exr".to_string(),
+        }
+    }
+}
+
+/// An image being rendered tile by tile,
+/// one sample of one tile each frame so the UI stays responsive
+/// and no submission runs long enough to trigger the GPU watchdog.
+pub struct TiledRender {
+    tiles: Vec<Viewport>,
+    /// The index of the tile being rendered.
+    pub current: usize,
+    passes: u32,
+    path: PathBuf,
+    image: image::Rgb32FImage,
+}
+
+impl TiledRender {
+    /// Split the image into tiles no larger than `max_tile_size`.
+    ///
+    /// # Errors
+    ///
+    /// If there isn't enough memory for the full image.
+    pub fn new(settings: &TileSettings, max_tile_size: u32) -> Result<Self> {
+        let (width, height) = settings.size;
+        let tile_size = settings.tile_size.clamp(1, max_tile_size);
+
+        let tiles = (0..height)
+            .step_by(tile_size as usize)
+            .flat_map(|y| {
+                (0..width)
+                    .step_by(tile_size as usize)
+                    .map(move |x| Viewport {
+                        offset: (x, y),
+                        size: (tile_size.min(width - x), tile_size.min(height - y)),
+                        full_size: (width, height),
+                    })
+            })
+            .collect();
+
+        // allocated up front, so a huge image is an error rather than an abort
+        let len = width as usize * height as usize * 3;
+        let mut data = Vec::new();
+        data.try_reserve_exact(len)
+            .with_context(|| format!("Not enough memory for a {width}x{height} image"))?;
+        data.resize(len, 0.);
+        let image = image::Rgb32FImage::from_raw(width, height, data)
+            .context("Image buffer is the wrong size")?;
+
+        Ok(Self {
+            tiles,
+            current: 0,
+            passes: settings.passes.max(1),
+            path: PathBuf::from(&settings.path),
+            image,
+        })
+    }
+
+    /// The number of tiles.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// The tile being rendered.
+    #[must_use]
+    pub fn viewport(&self) -> Viewport {
+        self.tiles[self.current]
+    }
+
+    /// Whether the current tile has all its passes.
+    #[must_use]
+    pub const fn is_tile_finished(&self, passes: u32) -> bool {
+        passes >= self.passes
+    }
+
+    /// Copy the finished tile into the image and move onto the next one,
+    /// returning whether every tile is finished.
+    pub fn add_tile(&mut self, tile: &image::Rgb32FImage) -> bool {
+        puffin::profile_function!();
+
+        let Viewport { offset, .. } = self.viewport();
+        for (x, y, pixel) in tile.enumerate_pixels() {
+            self.image.put_pixel(offset.0 + x, offset.1 + y, *pixel);
+        }
+
+        self.current += 1;
+        self.current == self.tiles.len()
+    }
+
+    /// Save the stitched image, returning where it was saved.
+    ///
+    /// # Errors
+    ///
+    /// If the file couldn't be written.
+    pub fn save(&self) -> Result<&PathBuf> {
+        puffin::profile_function!();
+
+        self.image
+            .save(&self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+
+        Ok(&self.path)
+    }
+}
@@ -1,18 +1,47 @@
 use anyhow::Result;
-use nalgebra::Rotation3;
 use puffin::GlobalFrameView;
 
 use crate::{
-    panels::{object_panel, settings_panel},
-    ray_tracer::{Geometry, Scene},
+    bindings::Action,
+    controls::Controls,
+    movement::{dolly, mouse_look, orbit, pan, CameraMode},
+    panels::{
+        bindings_widget, controls_widget, object_panel, settings_panel, timeline_panel, KeyEditor,
+    },
+    progress::Progress,
+    ray_tracer::{Physics, Scene, Vec3, Views},
+    settings::Settings,
     time::now_millis,
 };
 
+/// The keys which recall the first nine views.
+const NUMBER_KEYS: [egui::Key; 9] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+    egui::Key::Num7,
+    egui::Key::Num8,
+    egui::Key::Num9,
+];
+
 /// The UI state.
 pub struct Ui {
     last_time: f64,
     global_frame_view: GlobalFrameView,
     show_profiler: bool,
+    key_editor: KeyEditor,
+    physics: Physics,
+    views: Views,
+    camera_mode: CameraMode,
+    controls: Controls,
+    /// Whether the controls changed since they were last saved.
+    controls_changed: bool,
+    show_controls: bool,
+    /// The action waiting for a key to be pressed to bind to it.
+    rebinding: Option<Action>,
 }
 
 impl Ui {
@@ -26,6 +55,17 @@ impl Ui {
             last_time: now_millis()?,
             global_frame_view: GlobalFrameView::default(),
             show_profiler: true,
+            key_editor: KeyEditor::default(),
+            physics: Physics::new(),
+            views: Views::new(),
+            camera_mode: CameraMode::Fly,
+            controls: Controls::load().unwrap_or_else(|error| {
+                eprintln!("Loading the controls failed with error: {error:#}");
+                Controls::default()
+            }),
+            controls_changed: false,
+            show_controls: false,
+            rebinding: None,
         })
     }
 
@@ -38,8 +78,9 @@ impl Ui {
         &mut self,
         ctx: &egui::Context,
         render_target: &mut crate::gpu::RenderTarget,
-        device: &wgpu::Device,
         scene: &mut Scene,
+        settings: &mut Settings,
+        progress: &Progress,
     ) -> Result<()> {
         puffin::profile_function!();
 
@@ -48,74 +89,224 @@ impl Ui {
         let delta_time = (now - self.last_time) as f32 / 1000.;
         self.last_time = now;
 
-        ctx.input(|input_state| {
-            crate::movement::move_and_rotate(
-                input_state,
-                &mut scene.camera,
-                delta_time * 1.5,
-                delta_time * 20.,
-                6.,
-                0.4,
-            );
-        });
-
-        if scene.do_objects_spin {
-            let theta: f32 = 0.5 * std::f32::consts::PI * delta_time;
-            let rotation = Rotation3::from_euler_angles(0., theta, 0.);
-
-            scene.objects.iter_mut().for_each(|object| {
-                if let Geometry::Sphere { .. } = object.geometry {
-                } else {
-                    return;
-                }
+        if self.camera_mode == CameraMode::Fly && self.rebinding.is_none() {
+            ctx.input(|input_state| {
+                crate::movement::move_and_rotate(
+                    input_state,
+                    &mut scene.camera,
+                    &self.controls,
+                    delta_time,
+                );
+            });
+        }
 
-                let position = object.geometry.position_as_mut();
-                let length = position.magnitude();
+        // number keys recall the first nine views, unless typing
+        if !ctx.wants_keyboard_input() {
+            let recalled = ctx.input(|input_state| {
+                NUMBER_KEYS
+                    .iter()
+                    .position(|key| input_state.key_pressed(*key))
+            });
+            if let Some(index) = recalled {
+                self.views.go_to(scene, index);
+            }
+        }
+        self.views.update(scene, delta_time);
 
-                // *position = position.transform_point(rotation);
-                *position = (rotation * *position).xyz();
+        scene.animate(delta_time);
+        self.physics.update(scene, delta_time);
 
-                // correct for rounding errors
-                *position *= length / position.magnitude();
-            });
+        scene.apply_physical_camera();
+        if let Some(physical) = &scene.physical_camera {
+            settings.display.exposure = physical.exposure();
         }
 
         if puffin::are_scopes_on() && self.show_profiler {
             self.show_profiler = puffin_egui::profiler_window(ctx);
         }
 
+        self.controls_window(ctx);
+
+        egui::TopBottomPanel::bottom("timeline_panel").show(ctx, |ui| {
+            timeline_panel(ui, scene, &mut self.key_editor);
+        });
+
         egui::SidePanel::right("settings_panel")
             .default_width(400.)
             .show(ctx, |ui| {
-                settings_panel(ui, &self.global_frame_view, &mut self.show_profiler, scene);
+                settings_panel(
+                    ui,
+                    &self.global_frame_view,
+                    &mut self.show_profiler,
+                    scene,
+                    &mut self.physics,
+                    settings,
+                    progress,
+                );
             });
 
         egui::SidePanel::right("object_panel").show(ctx, |ui| {
-            object_panel(ui, scene);
+            object_panel(ui, scene, &mut self.views, &mut self.camera_mode);
         });
 
         if let Some(id) = render_target.id {
             egui::CentralPanel::default().show(ctx, |ui| {
+                self.camera_mode_widget(ui, scene);
+
                 egui::Resize::default()
                     .default_size([render_target.size.0 as f32, render_target.size.1 as f32])
                     .min_size([1., 1.])
                     .show(ui, |ui| {
-                        let size = (ui.available_size().x as u32, ui.available_size().y as u32);
-                        if size != render_target.size {
-                            render_target.resize(device, size);
-                        }
-
-                        ui.image(egui::ImageSource::Texture(egui::load::SizedTexture {
-                            id,
-                            size: egui::Vec2::new(
-                                render_target.size.0 as f32,
-                                render_target.size.1 as f32,
-                            ),
-                        }));
+                        let available = ui.available_size();
+                        render_target.requested_size = (available.x as u32, available.y as u32);
+
+                        // tiles can be a different shape to the panel
+                        let response = ui.add(
+                            egui::Image::new(egui::load::SizedTexture {
+                                id,
+                                size: egui::Vec2::new(
+                                    render_target.size.0 as f32,
+                                    render_target.size.1 as f32,
+                                ),
+                            })
+                            .max_size(available)
+                            .sense(egui::Sense::click_and_drag()),
+                        );
+
+                        self.viewport_input(ui, &response, scene);
+                        self.draw_pivot(ui, response.rect, scene);
                     });
             });
         }
 
+        self.save_changed_controls(ctx);
+
         Ok(())
     }
+
+    fn camera_mode_widget(&mut self, ui: &mut egui::Ui, scene: &Scene) {
+        ui.horizontal(|ui| {
+            ui.label("Camera");
+            if ui
+                .selectable_label(self.camera_mode == CameraMode::Fly, CameraMode::Fly.name())
+                .clicked()
+            {
+                self.camera_mode = CameraMode::Fly;
+            }
+            let is_orbit = matches!(self.camera_mode, CameraMode::Orbit { .. });
+            if ui.selectable_label(is_orbit, "Orbit").clicked() && !is_orbit {
+                // turn around whatever is in the middle of the view
+                let pivot = scene.pick((0.5, 0.5), 1.).map_or_else(
+                    || scene.camera.position - scene.camera.get_vectors_fru().0 * 5.,
+                    |(_, point)| point,
+                );
+                self.camera_mode = CameraMode::Orbit { pivot };
+            }
+
+            ui.toggle_value(&mut self.show_controls, "⚙ Controls");
+
+            if is_orbit {
+                ui.weak("drag to orbit, middle drag to pan, scroll to zoom, click to pick a pivot");
+            } else {
+                ui.label(format!("Speed {:.2}", self.controls.move_speed));
+                ui.weak("scroll to change speed, right drag to look");
+            }
+        });
+    }
+
+    fn controls_window(&mut self, ctx: &egui::Context) {
+        let mut changed = false;
+
+        egui::Window::new("Controls")
+            .open(&mut self.show_controls)
+            .show(ctx, |ui| {
+                changed |= controls_widget(ui, &mut self.controls);
+                ui.separator();
+                changed |= bindings_widget(ui, &mut self.controls.bindings, &mut self.rebinding);
+            });
+
+        if !self.show_controls {
+            self.rebinding = None;
+        }
+        self.controls_changed |= changed;
+    }
+
+    /// Save the controls once they've stopped changing,
+    /// rather than every frame a value is dragged or the speed is scrolled.
+    fn save_changed_controls(&mut self, ctx: &egui::Context) {
+        let scrolling = ctx.input(|input| input.smooth_scroll_delta != egui::Vec2::ZERO);
+        if !self.controls_changed || scrolling || ctx.is_using_pointer() {
+            return;
+        }
+
+        self.controls_changed = false;
+        if let Err(error) = self.controls.save() {
+            eprintln!("Saving the controls failed with error: {error:#}");
+        }
+    }
+
+    /// Move the camera with the mouse over the viewport.
+    fn viewport_input(&mut self, ui: &egui::Ui, response: &egui::Response, scene: &mut Scene) {
+        let scroll = if response.hovered() {
+            ui.input(|input| input.smooth_scroll_delta.y)
+        } else {
+            0.
+        };
+
+        match &mut self.camera_mode {
+            CameraMode::Fly => {
+                if response.dragged_by(egui::PointerButton::Secondary) {
+                    mouse_look(&mut scene.camera, &self.controls, response.drag_delta());
+                }
+                if scroll != 0. {
+                    self.controls.scroll_move_speed(scroll);
+                    self.controls_changed = true;
+                }
+            }
+            CameraMode::Orbit { pivot } => orbit_input(response, scroll, scene, pivot),
+        }
+    }
+
+    /// Mark the pivot on the viewport while orbiting.
+    fn draw_pivot(&self, ui: &egui::Ui, rect: egui::Rect, scene: &Scene) {
+        let CameraMode::Orbit { pivot } = self.camera_mode else {
+            return;
+        };
+        let Some((x, y)) = scene.camera.project(pivot, rect.aspect_ratio()) else {
+            return;
+        };
+
+        let position = rect.lerp_inside(egui::vec2(x, y));
+        if rect.contains(position) {
+            let painter = ui.painter_at(rect);
+            painter.circle_stroke(position, 6., egui::Stroke::new(2., egui::Color32::BLACK));
+            painter.circle_stroke(position, 6., egui::Stroke::new(1., egui::Color32::WHITE));
+            painter.circle_filled(position, 1.5, egui::Color32::WHITE);
+        }
+    }
+}
+
+/// Orbit, pan and dolly the camera with the mouse over the viewport.
+fn orbit_input(response: &egui::Response, scroll: f32, scene: &mut Scene, pivot: &mut Vec3) {
+    let delta = response.drag_delta();
+    if response.dragged_by(egui::PointerButton::Primary) {
+        orbit(&mut scene.camera, *pivot, delta.x * 0.01, delta.y * 0.01);
+    }
+    if response.dragged_by(egui::PointerButton::Middle) {
+        pan(&mut scene.camera, pivot, delta.x, delta.y);
+    }
+
+    if scroll != 0. {
+        dolly(&mut scene.camera, *pivot, scroll);
+    }
+
+    if response.clicked() {
+        if let Some(position) = response.interact_pointer_pos() {
+            let rect = response.rect;
+            let coord = (position - rect.min) / rect.size();
+            if let Some((_, point)) = scene.pick((coord.x, coord.y), rect.aspect_ratio()) {
+                *pivot = point;
+            }
+        }
+    }
 }